@@ -1,42 +1,125 @@
 use std::{
+    cell::RefCell,
     io::Error,
     panic::{set_hook, take_hook},
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
-use crossterm::event::{read, Event};
+use crossterm::event::{poll, read, Event};
 
+mod bufferpicker;
+mod clipboard;
 mod command;
 mod commandbar;
+mod config;
+mod filetype;
 mod messagebar;
 mod statusbar;
 mod terminal;
+mod theme;
 mod uicomponent;
 mod view;
+mod windows;
 
-use command::{Action, Command, Edit};
+use bufferpicker::BufferPicker;
+use clipboard::Clipboard;
+use command::{Action, Command, Edit, Move, SearchToggle};
 use commandbar::CommandBar;
+use config::Config;
 use messagebar::MessageBar;
-use statusbar::StatusBar;
 use terminal::{Position, Size, Terminal};
+use theme::Theme;
 use uicomponent::UIComponent;
-use view::View;
+use view::{buffer::Buffer, search::SearchOptions, SharedBuffer, View};
+use windows::{SplitDirection, Windows};
 
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct DocumentStatus {
     curr_location: view::location::Location,
+    //column the cursor is shown at, differs from the grapheme index after tabs and wide graphemes
+    display_column: usize,
     filename: Option<String>,
     is_modified: bool,
+    line_ending: view::lineending::LineEnding,
+    encoding: view::encoding::Encoding,
+    mixed_line_endings: bool,
+    total_lines: usize,
+    //lines of a streamed file are still being counted
+    is_indexing: bool,
+    //percent of a streamed file loaded for editing so far
+    load_progress: Option<u64>,
+    //cursor offset in the hex view of binary files
+    byte_offset: Option<usize>,
 }
 
-#[derive(Default)]
+//how long to wait for input before doing background work
+const TICK: Duration = Duration::from_millis(100);
+//how often to look for changes to the open file by other programs
+//and to write unsaved edits to the swap file
+const BACKGROUND_INTERVAL: Duration = Duration::from_secs(2);
+
+const FILE_CHANGED_PROMPT: &str = "file changed on disk: [r]eload [o]verwrite [d]iff [c]ancel ";
+const RECOVER_PROMPT: &str = "unsaved edits found in swap file: [r]ecover [d]iff [x] discard ";
+const REPLACE_CONFIRM_PROMPT: &str = "replace this match? [y]es [n]o [a]ll [q]uit ";
+
+//what the command bar is asking for
+#[derive(Default, Clone, Copy)]
+enum PromptType {
+    #[default]
+    Save,
+    //`saving` if the change was noticed when saving
+    FileChanged {
+        saving: bool,
+    },
+    //a swap file was left behind by an earlier session
+    Recover,
+    SwitchBuffer,
+    Search,
+    //the pattern, then what to replace it with, then yes or no for every match
+    Replace,
+    ReplaceWith,
+    ReplaceConfirm,
+}
+
+//`buffers` are all open buffers in the order they were opened,
+//any number of panes in `windows` may show each of them
 pub struct Editor {
     should_quit: bool,
-    view: View,
-    statusbar: StatusBar,
+    buffers: Vec<SharedBuffer>,
+    windows: Windows,
+    buffer_picker: Option<BufferPicker>,
     messagebar: MessageBar,
     command_bar: Option<CommandBar>,
+    prompt_type: PromptType,
+    //kept for the next search
+    search_options: SearchOptions,
+    //shared by all panes and buffers
+    clipboard: Clipboard,
     title: String,
     size: Size,
+    config: Config,
+    last_background_work: Instant,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            should_quit: false,
+            buffers: Vec::new(),
+            windows: Windows::default(),
+            buffer_picker: None,
+            messagebar: MessageBar::default(),
+            command_bar: None,
+            prompt_type: PromptType::default(),
+            search_options: SearchOptions::default(),
+            clipboard: Clipboard::default(),
+            title: String::new(),
+            size: Size::default(),
+            config: Config::default(),
+            last_background_work: Instant::now(),
+        }
+    }
 }
 
 impl Drop for Editor {
@@ -59,25 +142,196 @@ impl Editor {
         Terminal::initialize()?;
 
         let mut editor = Editor::default();
+
+        match Config::load() {
+            Ok(config) => {
+                editor.clipboard = Clipboard::new(config.clipboard_settings());
+                editor.config = config;
+            }
+            Err(err) => editor
+                .messagebar
+                .update_message(&format!("couldnot load config: {err}")),
+        }
+        match Theme::load(&editor.config) {
+            Ok(theme) => theme.install(),
+            Err(err) => editor
+                .messagebar
+                .update_message(&format!("couldnot load theme: {err}")),
+        }
+
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        editor.open_files(&args);
+
         let size = Terminal::size().unwrap_or_default();
         editor.resize(size);
+        editor.show_buffer();
+        editor.update_status();
+        Ok(editor)
+    }
 
-        let args: Vec<String> = std::env::args().collect();
-        if let Some(file) = args.get(1) {
-            if editor.view.load(file).is_err() {
-                editor
-                    .messagebar
-                    .update_message("couldnot load file {file}");
+    //files that can't be loaded are skipped, without any file there is one empty buffer
+    fn open_files(&mut self, files: &[String]) {
+        let mut errors = Vec::new();
+        let mut opened = Vec::new();
+        for file in files {
+            //the same file twice would be two buffers fighting over one file on disk
+            let path = std::fs::canonicalize(file)
+                .or_else(|_| std::path::absolute(file))
+                .unwrap_or_else(|_| file.into());
+            if opened.contains(&path) {
+                continue;
+            }
+            opened.push(path);
+            let mut buffer = match Buffer::load(file, &self.config) {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    errors.push(format!("couldnot load file {file}: {err}"));
+                    continue;
+                }
             };
+            if let Err(err) = buffer.open_swap() {
+                errors.push(format!(
+                    "unsaved edits of {file} won't be kept in a swap file: {err}"
+                ));
+            }
+            self.buffers.push(Rc::new(RefCell::new(buffer)));
+        }
+        if self.buffers.is_empty() {
+            self.buffers.push(SharedBuffer::default());
         }
+        let first = self.buffers.first().cloned().unwrap_or_default();
+        self.windows = Windows::new(View::new(first));
 
-        editor.update_status();
-        Ok(editor)
+        if !errors.is_empty() {
+            self.messagebar.update_message(&errors.join(", "));
+        } else if let Some(message) = self.load_message() {
+            self.messagebar.update_message(&message);
+        }
+    }
+
+    fn load_message(&self) -> Option<String> {
+        let view = self.windows.view();
+        if view.is_streaming() {
+            Some("large file, it is loaded fully in the background on the first edit".to_string())
+        } else if view.is_binary() {
+            Some("binary file, opened in hex view".to_string())
+        } else if view.get_status().mixed_line_endings {
+            let line_ending = view.line_ending();
+            Some(format!(
+                "mixed line endings, kept as they are unless converted to {line_ending}"
+            ))
+        } else {
+            None
+        }
+    }
+
+    //position of the buffer shown in the focused pane
+    fn current_buffer(&self) -> usize {
+        let shown = self.windows.view().shared_buffer();
+        self.buffers
+            .iter()
+            .position(|buffer| Rc::ptr_eq(buffer, shown))
+            .unwrap_or_default()
+    }
+
+    fn buffer_name(buffer: &SharedBuffer) -> String {
+        let buffer = buffer.borrow();
+        let name = buffer.filename.as_deref().unwrap_or("unnamed");
+        if buffer.is_modified() {
+            format!("{name} [+]")
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn switch_buffer(&mut self, index: usize) {
+        if index == self.current_buffer() {
+            return;
+        }
+        let Some(buffer) = self.buffers.get(index).cloned() else {
+            return;
+        };
+        self.windows.view_mut().set_buffer(buffer);
+        self.show_buffer();
+    }
+
+    fn next_buffer(&mut self) {
+        let index = self
+            .current_buffer()
+            .saturating_add(1)
+            .checked_rem(self.buffers.len())
+            .unwrap_or_default();
+        self.switch_buffer(index);
+    }
+
+    fn previous_buffer(&mut self) {
+        let index = self
+            .current_buffer()
+            .checked_sub(1)
+            .unwrap_or(self.buffers.len().saturating_sub(1));
+        self.switch_buffer(index);
+    }
+
+    //an exact name wins, otherwise the name has to be part of exactly one buffer name
+    fn switch_buffer_by_name(&mut self, name: &str) {
+        let names: Vec<Option<String>> = self
+            .buffers
+            .iter()
+            .map(|buffer| buffer.borrow().filename.clone())
+            .collect();
+        let index = names
+            .iter()
+            .position(|filename| filename.as_deref() == Some(name))
+            .or_else(|| {
+                let mut matching = names.iter().enumerate().filter(|(_, filename)| {
+                    filename
+                        .as_ref()
+                        .is_some_and(|filename| filename.contains(name))
+                });
+                match (matching.next(), matching.next()) {
+                    (Some((index, _)), None) => Some(index),
+                    _ => None,
+                }
+            });
+        match index {
+            Some(index) => self.switch_buffer(index),
+            None => self
+                .messagebar
+                .update_message(&format!("no single buffer matches {name}")),
+        }
+    }
+
+    //shown in place of the focused view
+    fn show_buffer_picker(&mut self) {
+        let entries = self.buffers.iter().map(Self::buffer_name).collect();
+        let mut picker = BufferPicker::new(entries, self.current_buffer());
+        picker.resize(self.windows.focused().view_size());
+        self.buffer_picker = Some(picker);
+    }
+
+    fn dismiss_buffer_picker(&mut self) {
+        self.buffer_picker = None;
+        self.windows.view_mut().mark_redraw(true);
+    }
+
+    //checks that were skipped while the buffer was hidden
+    fn show_buffer(&mut self) {
+        self.windows.view_mut().mark_redraw(true);
+        self.check_disk();
+        if self.command_bar.is_none() && self.windows.view().has_stale_swap() {
+            self.show_prompt(PromptType::Recover);
+        }
+    }
+
+    fn close_pane(&mut self) {
+        if !self.windows.close_focused() {
+            self.messagebar
+                .update_message("the last pane can't be closed, quit instead");
+        }
     }
 
     fn update_status(&mut self) {
-        let status = self.view.get_status();
-        self.statusbar.update_status(status);
+        self.windows.update_status();
     }
 
     fn render_title(&mut self) {
@@ -90,39 +344,53 @@ impl Editor {
             if self.should_quit {
                 break;
             }
-            match read() {
-                Ok(event) => {
+            match poll(TICK).and_then(|ready| ready.then(read).transpose()) {
+                Ok(Some(event)) => {
                     self.handle_event(event);
                 }
+                Ok(None) => self.windows.views_mut().for_each(View::tick),
                 Err(err) => {
                     #[cfg(debug_assertions)]
                     panic!("couldn't read event: {err:?}");
                 }
             }
+            self.finish_full_loads();
+            if self.last_background_work.elapsed() >= BACKGROUND_INTERVAL {
+                self.check_disk();
+                self.write_swap();
+                self.last_background_work = Instant::now();
+            }
+            //edits and reloads show up in every pane of the buffer
+            self.windows.views_mut().for_each(View::sync);
             self.update_status();
         }
     }
 
     fn process_command(&mut self, command: Command) {
         //if command bar exists, forward corresspongin event to commandbar
+        if self.buffer_picker.is_some() {
+            self.handle_picker_command(command);
+            return;
+        }
 
         match command {
             Command::Edit(command) => {
-                if let Some(command_bar) = self.command_bar.as_mut() {
-                    if matches!(command, Edit::InsertNewLine) {
-                        let filename = command_bar.get_input();
-                        self.dismiss_prompt();
-                        self.save_file(Some(filename));
-                    } else {
-                        command_bar.handle_edit_command(command);
-                    }
-                } else {
-                    self.view.handle_edit_command(command);
+                if self.command_bar.is_some() {
+                    self.handle_prompt_edit(command);
+                } else if self.ensure_fully_loaded() {
+                    self.windows.view_mut().handle_edit_command(command);
                 }
             }
             Command::Move(command) => {
                 if self.command_bar.is_none() {
-                    self.view.handle_move_command(command);
+                    self.windows.view_mut().handle_move_command(command);
+                } else if let PromptType::Search | PromptType::Replace = self.prompt_type {
+                    self.handle_search_move(command);
+                }
+            }
+            Command::Select(command) => {
+                if self.command_bar.is_none() {
+                    self.windows.view_mut().handle_select_command(command);
                 }
             }
             Command::Action(command) => self.handle_action_command(command),
@@ -131,74 +399,582 @@ impl Editor {
 
     fn handle_action_command(&mut self, action: command::Action) {
         match action {
+            Action::Resize(size) => self.resize(size),
+            Action::ToggleLineEnding => self.toggle_line_ending(),
+            //a prompt always belongs to the focused buffer and is answered or dismissed
+            //first, saving or quitting halfway through a replace would skip finishing it
+            Action::Save
+            | Action::Quit
+            | Action::ForceQuit
+            | Action::NextBuffer
+            | Action::PreviousBuffer
+            | Action::SwitchBuffer
+            | Action::ListBuffers
+            | Action::Search
+            | Action::Replace
+            | Action::Copy
+            | Action::Cut
+            | Action::Paste
+            | Action::Split(_)
+            | Action::ClosePane
+            | Action::FocusNextPane
+            | Action::Focus(_)
+                if self.command_bar.is_some() => {}
             Action::Save => self.handle_save(),
             Action::Quit => self.handle_quit(),
             Action::ForceQuit => self.handle_force_quit(),
-            Action::Resize(size) => self.resize(size),
+            Action::NextBuffer => self.next_buffer(),
+            Action::PreviousBuffer => self.previous_buffer(),
+            Action::SwitchBuffer => self.show_prompt(PromptType::SwitchBuffer),
+            Action::ListBuffers => self.show_buffer_picker(),
+            Action::Search => self.start_search(PromptType::Search),
+            Action::Replace => {
+                if self.ensure_fully_loaded() {
+                    self.start_search(PromptType::Replace);
+                }
+            }
+            Action::Copy => self.copy(),
+            Action::Cut => self.cut(),
+            Action::Paste => self.paste(),
+            Action::Split(direction) => self.windows.split(direction),
+            Action::ClosePane => self.close_pane(),
+            Action::FocusNextPane => {
+                self.windows.focus_next();
+                self.show_buffer();
+            }
+            Action::Focus(direction) => {
+                self.windows.focus_towards(direction);
+                self.show_buffer();
+            }
+            Action::ResizePane(direction, grow) => self.windows.resize_focused(direction, grow),
             Action::Dismiss => {
                 if self.command_bar.is_some() {
+                    self.cancel_prompt();
+                }
+            }
+        }
+    }
+
+    //the picker takes all keys while it is open
+    fn handle_picker_command(&mut self, command: Command) {
+        let Some(picker) = self.buffer_picker.as_mut() else {
+            return;
+        };
+        match command {
+            Command::Move(direction) => picker.handle_move_command(direction),
+            Command::Edit(Edit::InsertNewLine) => {
+                let index = picker.selected();
+                self.dismiss_buffer_picker();
+                self.switch_buffer(index);
+            }
+            Command::Action(Action::Dismiss | Action::ListBuffers) => {
+                self.dismiss_buffer_picker();
+            }
+            Command::Action(Action::Resize(size)) => self.resize(size),
+            Command::Action(Action::ForceQuit) => self.handle_force_quit(),
+            Command::Edit(_) | Command::Select(_) | Command::Action(_) => {}
+        }
+    }
+
+    fn handle_prompt_edit(&mut self, command: Edit) {
+        match self.prompt_type {
+            PromptType::Save => {
+                if matches!(command, Edit::InsertNewLine) {
+                    let filename = self
+                        .command_bar
+                        .as_ref()
+                        .map(CommandBar::get_input)
+                        .unwrap_or_default();
+                    self.dismiss_prompt();
+                    self.save_file(Some(filename));
+                } else if let Some(command_bar) = self.command_bar.as_mut() {
+                    command_bar.handle_edit_command(command);
+                }
+            }
+            PromptType::SwitchBuffer => {
+                if matches!(command, Edit::InsertNewLine) {
+                    let name = self
+                        .command_bar
+                        .as_ref()
+                        .map(CommandBar::get_input)
+                        .unwrap_or_default();
                     self.dismiss_prompt();
-                    self.messagebar.update_message("Save Aborted");
+                    self.switch_buffer_by_name(&name);
+                } else if let Some(command_bar) = self.command_bar.as_mut() {
+                    command_bar.handle_edit_command(command);
+                }
+            }
+            PromptType::Search | PromptType::Replace => {
+                if !matches!(command, Edit::InsertNewLine) {
+                    if let Some(command_bar) = self.command_bar.as_mut() {
+                        command_bar.handle_edit_command(command);
+                    }
+                    self.update_search();
+                } else if let PromptType::Replace = self.prompt_type {
+                    self.show_prompt(PromptType::ReplaceWith);
+                } else {
+                    self.dismiss_prompt();
+                    self.windows.view_mut().exit_search();
+                }
+            }
+            PromptType::ReplaceWith => {
+                if matches!(command, Edit::InsertNewLine) {
+                    let text = self
+                        .command_bar
+                        .as_ref()
+                        .map(CommandBar::get_input)
+                        .unwrap_or_default();
+                    if self.windows.view_mut().start_replace(text) {
+                        self.show_prompt(PromptType::ReplaceConfirm);
+                    } else {
+                        self.finish_replace();
+                    }
+                } else if let Some(command_bar) = self.command_bar.as_mut() {
+                    command_bar.handle_edit_command(command);
+                }
+            }
+            PromptType::ReplaceConfirm => {
+                if let Edit::Insert(answer) = command {
+                    self.handle_replace(answer);
+                }
+            }
+            PromptType::FileChanged { .. } => {
+                if let Edit::Insert(answer) = command {
+                    self.handle_file_changed(answer);
+                }
+            }
+            PromptType::Recover => {
+                if let Edit::Insert(answer) = command {
+                    self.handle_recover(answer);
                 }
             }
         }
     }
+
+    fn cancel_prompt(&mut self) {
+        self.dismiss_prompt();
+        match self.prompt_type {
+            PromptType::Save | PromptType::FileChanged { saving: true } => {
+                self.messagebar.update_message("Save Aborted");
+            }
+            PromptType::FileChanged { saving: false } => {
+                self.messagebar
+                    .update_message("file changed on disk, keeping the edited version");
+            }
+            PromptType::Recover => {
+                self.messagebar
+                    .update_message("swap file kept, it is offered again when the buffer is shown");
+            }
+            PromptType::SwitchBuffer => {}
+            PromptType::Search | PromptType::Replace | PromptType::ReplaceWith => {
+                self.windows.view_mut().dismiss_search();
+            }
+            //replacements made so far are kept
+            PromptType::ReplaceConfirm => self.finish_replace(),
+        }
+        if let PromptType::FileChanged { .. } = self.prompt_type {
+            self.windows.view_mut().ignore_disk_change();
+        }
+    }
+
+    //the hex view has no text to search
+    fn start_search(&mut self, prompt_type: PromptType) {
+        if self.windows.view().is_binary() {
+            self.messagebar
+                .update_message("search is not available in the hex view");
+            return;
+        }
+        self.windows.view_mut().enter_search();
+        self.show_prompt(prompt_type);
+    }
+
+    fn search_prompt(&self, label: &str, valid: bool) -> String {
+        let options = self.search_options;
+        let invalid = if valid { "" } else { "invalid regex - " };
+        format!("{invalid}{label} ({options}, alt+r/c/w to toggle):")
+    }
+
+    //searches again whenever the query or the options change
+    fn update_search(&mut self) {
+        let query = self
+            .command_bar
+            .as_ref()
+            .map(CommandBar::get_input)
+            .unwrap_or_default();
+        let valid = self
+            .windows
+            .view_mut()
+            .search(&query, self.search_options)
+            .is_ok();
+        let label = if let PromptType::Replace = self.prompt_type {
+            "Replace"
+        } else {
+            "Search"
+        };
+        let prompt = self.search_prompt(label, valid);
+        if let Some(command_bar) = self.command_bar.as_mut() {
+            command_bar.update_prompt(&prompt);
+        }
+    }
+
+    fn toggle_search_option(&mut self, toggle: SearchToggle) {
+        let options = &mut self.search_options;
+        match toggle {
+            SearchToggle::Regex => options.regex = !options.regex,
+            SearchToggle::Case => options.case = options.case.next(),
+            SearchToggle::WholeWord => options.whole_word = !options.whole_word,
+        }
+        self.update_search();
+    }
+
+    fn handle_replace(&mut self, answer: char) {
+        let view = self.windows.view_mut();
+        let has_next = match answer {
+            'y' => view.replace_match(),
+            'n' => view.skip_match(),
+            'a' => {
+                view.replace_all();
+                false
+            }
+            'q' => false,
+            _ => return,
+        };
+        if !has_next {
+            self.finish_replace();
+        }
+    }
+
+    fn finish_replace(&mut self) {
+        self.dismiss_prompt();
+        let count = self.windows.view_mut().finish_replace();
+        let matches = if count == 1 { "match" } else { "matches" };
+        self.messagebar
+            .update_message(&format!("replaced {count} {matches}"));
+    }
+
+    //the text of streamed files is only there once they are loaded fully
+    fn copy(&mut self) {
+        if !self.ensure_fully_loaded() {
+            return;
+        }
+        match self.windows.view().selected_text() {
+            Some(text) => self.set_clipboard(text),
+            None => self.messagebar.update_message("nothing selected"),
+        }
+    }
+
+    fn cut(&mut self) {
+        if !self.ensure_fully_loaded() {
+            return;
+        }
+        match self.windows.view_mut().cut() {
+            Some(text) => self.set_clipboard(text),
+            None => self.messagebar.update_message("nothing selected"),
+        }
+    }
+
+    fn set_clipboard(&mut self, text: String) {
+        if let Err(err) = self.clipboard.set(text) {
+            self.messagebar
+                .update_message(&format!("copied, but not to the system clipboard: {err}"));
+        }
+    }
+
+    fn paste(&mut self) {
+        if self.clipboard.text().is_empty() || !self.ensure_fully_loaded() {
+            return;
+        }
+        self.windows.view_mut().paste(self.clipboard.text());
+    }
+
+    //up and left go to the previous match, down and right to the next one
+    fn handle_search_move(&mut self, direction: Move) {
+        match direction {
+            Move::Up | Move::Left => self.windows.view_mut().search_previous(),
+            Move::Down | Move::Right => self.windows.view_mut().search_next(),
+            _ => {}
+        }
+    }
+
+    fn handle_file_changed(&mut self, answer: char) {
+        match answer {
+            'r' => {
+                self.dismiss_prompt();
+                self.reload();
+            }
+            'o' => {
+                self.dismiss_prompt();
+                self.save_file(None);
+            }
+            'd' => {
+                let diff = self.windows.view().diff_with_disk(&self.config);
+                self.show_diff(diff, FILE_CHANGED_PROMPT);
+            }
+            'c' => self.cancel_prompt(),
+            _ => {}
+        }
+    }
+
+    fn handle_recover(&mut self, answer: char) {
+        match answer {
+            'r' => {
+                self.dismiss_prompt();
+                if !self.ensure_fully_loaded() {
+                    self.messagebar.update_message(
+                        "loading the file first, recovering is offered again once it is loaded",
+                    );
+                    return;
+                }
+                match self.windows.view_mut().recover_swap() {
+                    Ok(()) => self
+                        .messagebar
+                        .update_message("unsaved edits recovered, save to keep them"),
+                    Err(err) => self
+                        .messagebar
+                        .update_message(&format!("couldnot recover swap file: {err}")),
+                }
+            }
+            'd' => {
+                let diff = self.windows.view().diff_with_swap();
+                self.show_diff(diff, RECOVER_PROMPT);
+            }
+            'x' => {
+                self.dismiss_prompt();
+                match self.windows.view_mut().discard_swap() {
+                    Ok(()) => self.messagebar.update_message("swap file discarded"),
+                    Err(err) => self
+                        .messagebar
+                        .update_message(&format!("couldnot discard swap file: {err}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    //in a new pane below, the prompt stays open for the buffer it is about
+    fn show_diff(&mut self, diff: Result<Option<String>, Error>, prompt: &str) {
+        let outcome = match diff {
+            Ok(Some(diff)) => {
+                let buffer = Rc::new(RefCell::new(Buffer::scratch(&diff, &self.config)));
+                self.buffers.push(Rc::clone(&buffer));
+                self.windows
+                    .open_beside(SplitDirection::Horizontal, View::new(buffer));
+                "diff shown below".to_string()
+            }
+            Ok(None) => "no differences".to_string(),
+            Err(err) => format!("couldnot diff: {err}"),
+        };
+        if let Some(command_bar) = self.command_bar.as_mut() {
+            command_bar.update_prompt(&format!("{outcome} - {prompt}"));
+        }
+    }
+
+    fn write_swap(&mut self) {
+        let result = self
+            .buffers
+            .iter()
+            .try_for_each(|buffer| buffer.borrow_mut().write_swap());
+        if let Err(err) = result {
+            self.messagebar
+                .update_message(&format!("couldnot write swap file: {err}"));
+        }
+    }
+
+    fn reload(&mut self) {
+        match self.windows.view_mut().reload(&self.config) {
+            Ok(()) => self.messagebar.update_message("file reloaded from disk"),
+            Err(err) => self
+                .messagebar
+                .update_message(&format!("couldnot reload file: {err}")),
+        }
+    }
+
+    //unmodified buffers just follow the file on disk,
+    //modified ones are asked about once they are shown in the focused pane
+    fn check_disk(&mut self) {
+        let shown = self.windows.view().shared_buffer();
+        for buffer in &self.buffers {
+            if Rc::ptr_eq(buffer, shown) {
+                continue;
+            }
+            let mut buffer = buffer.borrow_mut();
+            if buffer.has_new_disk_change() && !buffer.is_modified() {
+                if let Err(err) = buffer.reload(&self.config) {
+                    let name = buffer.filename.as_deref().unwrap_or_default();
+                    self.messagebar
+                        .update_message(&format!("couldnot reload {name}: {err}"));
+                }
+            }
+        }
+        if self.command_bar.is_some() || !self.windows.view_mut().has_new_disk_change() {
+            return;
+        }
+        if self.windows.view().is_modified() {
+            self.show_prompt(PromptType::FileChanged { saving: false });
+        } else {
+            self.reload();
+        }
+    }
     fn save_file(&mut self, filename: Option<String>) {
         let result = if let Some(filename) = filename {
-            self.view.save_as(filename)
+            self.windows.view_mut().save_as(filename, &self.config)
         } else {
-            self.view.save()
+            self.windows.view_mut().save()
         };
-        if result.is_ok() {
-            self.messagebar.update_message("File saved successfully");
-        } else {
-            self.messagebar.update_message("Errow while saving file");
+        match result {
+            Ok(()) => self.messagebar.update_message("File saved successfully"),
+            Err(err) => self
+                .messagebar
+                .update_message(&format!("Error while saving file: {err}")),
         }
     }
     fn handle_save(&mut self) {
-        if self.view.is_unnamed() {
-            self.show_prompt("Save As:");
+        if self.windows.view().is_unnamed() {
+            self.show_prompt(PromptType::Save);
+        } else if self.windows.view_mut().changed_on_disk() {
+            self.show_prompt(PromptType::FileChanged { saving: true });
         } else {
             self.save_file(None);
         }
     }
 
+    //streamed files can't be edited in place, edits are refused
+    //until they are loaded fully in the background
+    fn ensure_fully_loaded(&mut self) -> bool {
+        if !self.windows.view().is_streaming() {
+            return true;
+        }
+        self.windows.view_mut().start_full_load();
+        self.messagebar
+            .update_message("loading the file for editing, it can be edited once that is done");
+        false
+    }
+
+    fn finish_full_loads(&mut self) {
+        let finished: Vec<(SharedBuffer, Result<(), Error>)> = self
+            .buffers
+            .iter()
+            .filter_map(|buffer| {
+                let result = buffer.borrow_mut().finish_full_load()?;
+                Some((Rc::clone(buffer), result))
+            })
+            .collect();
+        for (buffer, result) in finished {
+            let name = buffer.borrow().filename.clone().unwrap_or_default();
+            match result {
+                Ok(()) => self
+                    .messagebar
+                    .update_message(&format!("{name} is loaded, it can be edited now")),
+                Err(err) => self
+                    .messagebar
+                    .update_message(&format!("couldnot load {name} for editing: {err}")),
+            }
+            //a swap file can only be recovered now
+            let is_shown = Rc::ptr_eq(&buffer, self.windows.view().shared_buffer());
+            if is_shown && self.command_bar.is_none() && self.windows.view().has_stale_swap() {
+                self.show_prompt(PromptType::Recover);
+            }
+        }
+    }
+
+    fn toggle_line_ending(&mut self) {
+        if !self.ensure_fully_loaded() {
+            return;
+        }
+        //a file with mixed line endings is converted to the more common one first
+        let view = self.windows.view();
+        let line_ending = if view.get_status().mixed_line_endings {
+            view.line_ending()
+        } else {
+            view.line_ending().toggle()
+        };
+        self.windows.view_mut().set_line_ending(line_ending);
+        self.messagebar
+            .update_message(&format!("line endings set to {line_ending}"));
+    }
+
+    //quitting without saving gives up the unsaved edits, swap files included
     fn handle_force_quit(&mut self) {
+        self.discard_swaps();
         self.should_quit = true;
     }
+
+    //swap files left by an earlier session are kept until the user recovers or discards them
+    fn discard_swaps(&mut self) {
+        for buffer in &self.buffers {
+            let mut buffer = buffer.borrow_mut();
+            if !buffer.has_stale_swap() {
+                //nowhere to report it anymore, an orphaned swap file is offered for recovery later
+                let _ = buffer.discard_swap();
+            }
+        }
+    }
     fn handle_quit(&mut self) {
-        if self.view.is_modified() {
-            self.messagebar
-                .update_message("please save the file before closing");
-        } else {
+        let modified: Vec<String> = self
+            .buffers
+            .iter()
+            .map(|buffer| buffer.borrow())
+            .filter(|buffer| buffer.is_modified())
+            .map(|buffer| buffer.filename.clone().unwrap_or("unnamed".to_string()))
+            .collect();
+        if modified.is_empty() {
+            self.discard_swaps();
             self.should_quit = true;
+        } else {
+            let message = format!(
+                "please save the modified buffers before closing: {}",
+                modified.join(", ")
+            );
+            self.messagebar.update_message(&message);
         }
     }
 
     fn handle_event(&mut self, event: Event) {
+        if let (Event::Key(key), Some(_), PromptType::Search | PromptType::Replace) =
+            (&event, &self.command_bar, self.prompt_type)
+        {
+            if let Ok(toggle) = SearchToggle::try_from(*key) {
+                self.toggle_search_option(toggle);
+                return;
+            }
+        }
         if let Ok(command) = Command::try_from(event) {
             self.process_command(command);
         }
     }
-    fn show_prompt(&mut self, prompt: &str) {
+    fn show_prompt(&mut self, prompt_type: PromptType) {
+        let prompt = match prompt_type {
+            PromptType::Save => "Save As:",
+            PromptType::FileChanged { .. } => FILE_CHANGED_PROMPT,
+            PromptType::Recover => RECOVER_PROMPT,
+            PromptType::SwitchBuffer => "Switch to buffer:",
+            PromptType::Search => &self.search_prompt("Search", true),
+            PromptType::Replace => &self.search_prompt("Replace", true),
+            PromptType::ReplaceWith => "Replace with:",
+            PromptType::ReplaceConfirm => REPLACE_CONFIRM_PROMPT,
+        };
         let mut command_bar = CommandBar::default();
         command_bar.resize(self.size);
         command_bar.update_prompt(prompt);
         command_bar.mark_redraw(true);
         self.command_bar = Some(command_bar);
+        self.prompt_type = prompt_type;
     }
     fn dismiss_prompt(&mut self) {
         self.command_bar = None;
         self.messagebar.mark_redraw(true);
     }
+    //the panes get everything above the message bar
     pub fn resize(&mut self, size: Size) {
         self.size = size;
         let Size { width, height } = self.size;
-        self.view.resize(Size {
-            height: height.saturating_sub(2),
+        self.windows.resize(Size {
+            height: height.saturating_sub(1),
             width,
         });
-        self.statusbar.resize(Size { height: 1, width });
+        if let Some(picker) = self.buffer_picker.as_mut() {
+            picker.resize(self.windows.focused().view_size());
+        }
         if let Some(command_bar) = self.command_bar.as_mut() {
             command_bar.resize(Size { height: 1, width });
         } else {
@@ -215,17 +991,21 @@ impl Editor {
         let _ = Terminal::hide_caret();
 
         let bottom_row = self.size.height.saturating_sub(1);
+        let bottom = Position {
+            row: bottom_row,
+            col: 0,
+        };
         if let Some(command_bar) = self.command_bar.as_mut() {
-            command_bar.render(bottom_row);
+            command_bar.render(bottom);
         } else {
-            self.messagebar.render(bottom_row);
+            self.messagebar.render(bottom);
         }
 
         if self.size.height > 1 {
-            self.statusbar.render(self.size.height.saturating_sub(2));
-        }
-        if self.size.height > 2 {
-            self.view.render(0);
+            self.windows.render(self.buffer_picker.is_some());
+            if let Some(picker) = self.buffer_picker.as_mut() {
+                picker.render(self.windows.focused().origin());
+            }
         }
         //handle title too
 
@@ -234,8 +1014,15 @@ impl Editor {
                 row: bottom_row,
                 col: command_bar.get_caret_location(),
             }
+        } else if let Some(picker) = self.buffer_picker.as_ref() {
+            let origin = self.windows.focused().origin();
+            let caret = picker.get_caret_location();
+            Position {
+                row: origin.row.saturating_add(caret.row),
+                col: origin.col.saturating_add(caret.col),
+            }
         } else {
-            self.view.get_caret_location()
+            self.windows.caret()
         };
         let _ = Terminal::move_caret(caret_position);
         let _ = Terminal::show_caret();
@@ -0,0 +1,98 @@
+use super::{
+    command::Move,
+    terminal::{Position, Size, Terminal},
+    theme::theme,
+    uicomponent::UIComponent,
+};
+use std::io::Error;
+
+//list of open buffers shown in place of the view, the selected one is highlighted
+#[derive(Default)]
+pub struct BufferPicker {
+    entries: Vec<String>,
+    selected: usize,
+    redraw: bool,
+    size: Size,
+}
+
+impl BufferPicker {
+    pub fn new(entries: Vec<String>, selected: usize) -> Self {
+        Self {
+            selected: selected.min(entries.len().saturating_sub(1)),
+            entries,
+            redraw: true,
+            size: Size::default(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn handle_move_command(&mut self, direction: Move) {
+        let last = self.entries.len().saturating_sub(1);
+        let page = self.size.height.saturating_sub(1).max(1);
+        self.selected = match direction {
+            Move::Up | Move::Left | Move::WordLeft => self.selected.saturating_sub(1),
+            Move::Down | Move::Right | Move::WordRight => self.selected.saturating_add(1).min(last),
+            Move::PageUp => self.selected.saturating_sub(page),
+            Move::PageDown => self.selected.saturating_add(page).min(last),
+            Move::StartOfLine | Move::LeftUp => 0,
+            Move::EndOfLine | Move::RightUp => last,
+        };
+        self.mark_redraw(true);
+    }
+
+    //the list scrolls just enough to keep the selected entry visible
+    fn first_row(&self) -> usize {
+        self.selected
+            .saturating_add(1)
+            .saturating_sub(self.size.height)
+    }
+
+    pub fn get_caret_location(&self) -> Position {
+        Position {
+            row: self.selected.saturating_sub(self.first_row()),
+            col: 0,
+        }
+    }
+}
+
+impl UIComponent for BufferPicker {
+    fn mark_redraw(&mut self, redraw: bool) {
+        self.redraw = redraw;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+
+    fn draw(&self, origin: Position) -> Result<(), Error> {
+        let first_row = self.first_row();
+        let width = self.size.width;
+        for row in 0..self.size.height {
+            let index = first_row.saturating_add(row);
+            let at = Position {
+                row: origin.row.saturating_add(row),
+                col: origin.col,
+            };
+            match self.entries.get(index) {
+                Some(entry) => {
+                    let line = format!("{:>3}  {entry}", index.saturating_add(1));
+                    let style = if index == self.selected {
+                        theme().text.patch(theme().selection)
+                    } else {
+                        theme().text
+                    };
+                    Terminal::print_styled_at(at, width, style, &line)?;
+                }
+                None => Terminal::print_styled_at(at, width, theme().text, "~")?,
+            }
+        }
+        Ok(())
+    }
+}
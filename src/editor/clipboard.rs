@@ -0,0 +1,103 @@
+use super::config::ClipboardSettings;
+use super::terminal::Terminal;
+use std::io::{Error, ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+//text copied in the editor, handed on to the system clipboard so other programs can paste it.
+//pasting always uses the text copied here, the terminal doesn't let us read its clipboard
+#[derive(Default)]
+pub struct Clipboard {
+    text: String,
+    settings: ClipboardSettings,
+}
+
+impl Clipboard {
+    pub fn new(settings: ClipboardSettings) -> Self {
+        Self {
+            text: String::new(),
+            settings,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    //the text is kept either way, the error tells why the system clipboard didn't get it.
+    //a configured command gets every copy, the terminal only those up to osc52_max_size
+    pub fn set(&mut self, text: String) -> Result<(), Error> {
+        self.text = text;
+        let terminal = (self.text.len() <= self.settings.osc52_max_size)
+            .then(|| Terminal::set_clipboard(&base64(self.text.as_bytes())));
+        match (self.settings.command.as_deref(), terminal) {
+            (Some(command), terminal) => {
+                pipe_to(command, &self.text).and(terminal.unwrap_or(Ok(())))
+            }
+            (None, Some(terminal)) => terminal,
+            (None, None) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} bytes is more than osc52_max_size and no clipboard_command is set",
+                    self.text.len()
+                ),
+            )),
+        }
+    }
+}
+
+fn pipe_to(command: &str, text: &str) -> Result<(), Error> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!("{command} failed with {status}")))
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+//standard alphabet with padding, 3 bytes become 4 characters
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3).saturating_mul(4));
+    for chunk in data.chunks(3) {
+        let byte = |index: usize| chunk.get(index).copied().unwrap_or(0);
+        let group = u32::from_be_bytes([0, byte(0), byte(1), byte(2)]);
+        for (index, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if index > chunk.len() {
+                encoded.push('=');
+                continue;
+            }
+            let sextet = group.wrapping_shr(shift) & 0x3f;
+            let char = usize::try_from(sextet)
+                .ok()
+                .and_then(|sextet| BASE64_ALPHABET.get(sextet))
+                .copied()
+                .unwrap_or(b'=');
+            encoded.push(char::from(char));
+        }
+    }
+    encoded
+}
@@ -1,4 +1,5 @@
 use super::terminal::Size;
+use super::windows::{FocusDirection, SplitDirection};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 
 //to handle unconstructed warnings
@@ -15,6 +16,9 @@ pub enum Move {
     EndOfLine,
     PageUp,
     PageDown,
+    //to the start of the word before the cursor, or the end of the one after it
+    WordLeft,
+    WordRight,
 }
 
 impl TryFrom<KeyEvent> for Move {
@@ -28,29 +32,48 @@ impl TryFrom<KeyEvent> for Move {
             code, modifiers, ..
         } = event;
 
-        if modifiers != KeyModifiers::NONE {
-            return Err(format!("no corresponding move command for {event:?}"));
+        match (code, modifiers) {
+            (KeyCode::Up, KeyModifiers::NONE) => Ok(Up),
+            (KeyCode::Down, KeyModifiers::NONE) => Ok(Down),
+            (KeyCode::Right, KeyModifiers::NONE) => Ok(Right),
+            (KeyCode::Left, KeyModifiers::NONE) => Ok(Left),
+            (KeyCode::Right, KeyModifiers::CONTROL) => Ok(WordRight),
+            (KeyCode::Left, KeyModifiers::CONTROL) => Ok(WordLeft),
+            (KeyCode::PageUp, KeyModifiers::NONE) => Ok(PageUp),
+            (KeyCode::PageDown, KeyModifiers::NONE) => Ok(PageDown),
+            (KeyCode::Home, KeyModifiers::NONE) => Ok(StartOfLine),
+            (KeyCode::End, KeyModifiers::NONE) => Ok(EndOfLine),
+            _ => Err(format!("no corresponding move command for {event:?}")),
         }
-        match code {
-            KeyCode::Up => Ok(Up),
-            KeyCode::Down => Ok(Down),
-            KeyCode::Right => Ok(Right),
-            KeyCode::Left => Ok(Left),
-            KeyCode::PageUp => Ok(PageUp),
-            KeyCode::PageDown => Ok(PageDown),
-            KeyCode::Home => Ok(StartOfLine),
-            KeyCode::End => Ok(EndOfLine),
-            _ => Err(format!("no corresponding move command for {code:?}")),
+    }
+}
+
+impl Move {
+    //shift+motion extends the selection
+    fn try_select(event: KeyEvent) -> Result<Self, String> {
+        if !event.modifiers.contains(KeyModifiers::SHIFT) {
+            return Err(format!("no corresponding select command for {event:?}"));
         }
+        Self::try_from(KeyEvent {
+            modifiers: event.modifiers.difference(KeyModifiers::SHIFT),
+            ..event
+        })
     }
 }
 
 #[derive(Clone, Copy)]
 pub enum Edit {
     Insert(char),
+    //Tab, a tab or spaces to the next stop depending on the indentation of the buffer
+    Indent,
     InsertNewLine,
     Delete,
     DeleteBackward,
+    //up to the end of the word after the cursor or the start of the one before it
+    DeleteWord,
+    DeleteWordBackward,
+    Undo,
+    Redo,
 }
 
 impl TryFrom<KeyEvent> for Edit {
@@ -65,9 +88,17 @@ impl TryFrom<KeyEvent> for Edit {
         match (code, modifiers) {
             (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Ok(Insert(c)),
             (KeyCode::Delete, KeyModifiers::NONE) => Ok(Delete),
-            (KeyCode::Backspace, KeyModifiers::NONE) => Ok(DeleteBackward),
+            //terminals that send ^H for backspace report it as ctrl+h
+            (KeyCode::Backspace, KeyModifiers::NONE)
+            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => Ok(DeleteBackward),
+            (KeyCode::Delete, KeyModifiers::CONTROL) => Ok(DeleteWord),
+            (KeyCode::Backspace, KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                Ok(DeleteWordBackward)
+            }
             (KeyCode::Enter, KeyModifiers::NONE) => Ok(InsertNewLine),
-            (KeyCode::Tab, KeyModifiers::NONE) => Ok(Insert('\t')),
+            (KeyCode::Tab, KeyModifiers::NONE) => Ok(Indent),
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => Ok(Undo),
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => Ok(Redo),
             _ => Err(format!("no corresponding edit command for {code:?}")),
         }
     }
@@ -79,6 +110,22 @@ pub enum Action {
     Quit,
     ForceQuit,
     Dismiss,
+    ToggleLineEnding,
+    NextBuffer,
+    PreviousBuffer,
+    SwitchBuffer,
+    ListBuffers,
+    Search,
+    Replace,
+    Copy,
+    Cut,
+    Paste,
+    Split(SplitDirection),
+    ClosePane,
+    FocusNextPane,
+    Focus(FocusDirection),
+    //grow or shrink the focused pane within the split in that direction
+    ResizePane(SplitDirection, bool),
     Resize(Size),
 }
 
@@ -91,19 +138,74 @@ impl TryFrom<KeyEvent> for Action {
         let KeyEvent {
             code, modifiers, ..
         } = event;
+        //pane commands use alt, shifted characters like '+' may come with shift too
+        if modifiers.contains(KeyModifiers::ALT) && !modifiers.contains(KeyModifiers::CONTROL) {
+            return match code {
+                KeyCode::Char('s') => Ok(Split(SplitDirection::Horizontal)),
+                KeyCode::Char('v') => Ok(Split(SplitDirection::Vertical)),
+                KeyCode::Char('c') => Ok(ClosePane),
+                KeyCode::Char('w') => Ok(FocusNextPane),
+                KeyCode::Up => Ok(Focus(FocusDirection::Up)),
+                KeyCode::Down => Ok(Focus(FocusDirection::Down)),
+                KeyCode::Left => Ok(Focus(FocusDirection::Left)),
+                KeyCode::Right => Ok(Focus(FocusDirection::Right)),
+                KeyCode::Char('+') => Ok(ResizePane(SplitDirection::Horizontal, true)),
+                KeyCode::Char('-') => Ok(ResizePane(SplitDirection::Horizontal, false)),
+                KeyCode::Char('>') => Ok(ResizePane(SplitDirection::Vertical, true)),
+                KeyCode::Char('<') => Ok(ResizePane(SplitDirection::Vertical, false)),
+                _ => Err(format!("no corresponding action command for {event:?}")),
+            };
+        }
         match (code, modifiers) {
             (KeyCode::Char('q'), KeyModifiers::CONTROL) => Ok(Quit),
             (KeyCode::Char('w'), KeyModifiers::CONTROL) => Ok(ForceQuit),
             (KeyCode::Char('s' | 'o'), KeyModifiers::CONTROL) => Ok(Save),
             (KeyCode::Esc, KeyModifiers::NONE) => Ok(Dismiss),
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => Ok(ToggleLineEnding),
+            (KeyCode::Char('n'), KeyModifiers::CONTROL) => Ok(NextBuffer),
+            (KeyCode::Char('p'), KeyModifiers::CONTROL) => Ok(PreviousBuffer),
+            (KeyCode::Char('g'), KeyModifiers::CONTROL) => Ok(SwitchBuffer),
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => Ok(ListBuffers),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => Ok(Search),
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => Ok(Replace),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Ok(Copy),
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => Ok(Cut),
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => Ok(Paste),
             _ => Err(format!("no corresponding action command for {event:?}")),
         }
     }
 }
 
+//options switched from the search and replace prompts, these alt keys only mean something there
+#[derive(Clone, Copy)]
+pub enum SearchToggle {
+    Regex,
+    Case,
+    WholeWord,
+}
+
+impl TryFrom<KeyEvent> for SearchToggle {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        #[allow(clippy::enum_glob_use)]
+        use SearchToggle::*;
+
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        match (code, modifiers) {
+            (KeyCode::Char('r'), KeyModifiers::ALT) => Ok(Regex),
+            (KeyCode::Char('c'), KeyModifiers::ALT) => Ok(Case),
+            (KeyCode::Char('w'), KeyModifiers::ALT) => Ok(WholeWord),
+            _ => Err(format!("no corresponding search toggle for {event:?}")),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Command {
     Move(Move),
+    Select(Move),
     Edit(Edit),
     Action(Action),
 }
@@ -121,6 +223,7 @@ impl TryFrom<Event> for Command {
             Event::Key(key_event) => Edit::try_from(key_event)
                 .map(Command::Edit)
                 .or_else(|_| Move::try_from(key_event).map(Command::Move))
+                .or_else(|_| Move::try_select(key_event).map(Command::Select))
                 .or_else(|_| Action::try_from(key_event).map(Command::Action))
                 .map_err(|_| format!("no corresponding command for {event:?}")),
             _ => Err(format!("no corresponding action command for {event:?}")),
@@ -1,6 +1,7 @@
 use super::{
     command::Edit,
-    terminal::{Size, Terminal},
+    terminal::{Position, Size, Terminal},
+    theme::theme,
     uicomponent::UIComponent,
     view::line::Line,
 };
@@ -18,8 +19,10 @@ impl CommandBar {
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
             Edit::Insert(char) => self.input.append_char(char),
+            Edit::Indent => self.input.append_char('\t'),
             Edit::DeleteBackward => self.input.remove_last(),
-            Edit::Delete | Edit::InsertNewLine => {}
+            Edit::DeleteWordBackward => self.input.remove_word_backward(),
+            Edit::Delete | Edit::DeleteWord | Edit::InsertNewLine | Edit::Undo | Edit::Redo => {}
         }
         self.mark_redraw(true);
     }
@@ -57,13 +60,13 @@ impl UIComponent for CommandBar {
         self.size = size;
     }
 
-    fn draw(&self, y_position: usize) -> Result<(), Error> {
+    fn draw(&self, origin: Position) -> Result<(), Error> {
         let input_render_len = self.size.width.saturating_sub(self.prompt.len());
         let right = self.input.width();
         let left = right.saturating_sub(input_render_len);
         let truncated_input = self.input.get_graphemes(left..right);
         let command_line = format!("{}{}", self.prompt, truncated_input);
-        Terminal::print_row(y_position, &command_line)?;
+        Terminal::print_styled_at(origin, self.size.width, theme().message_bar, &command_line)?;
         Ok(())
     }
 }
@@ -0,0 +1,255 @@
+use super::filetype::FileType;
+use super::terminal::style::ColorDepth;
+use super::view::gutter::LineNumbers;
+use super::view::indent::Indentation;
+use super::view::line::DEFAULT_TAB_WIDTH;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+//config file: $XDG_CONFIG_HOME/mim/config or ~/.config/mim/config
+//
+//  # comment
+//  insert_final_newline = true   global option
+//  [python]                      options below only apply to python files
+//  insert_final_newline = false
+//  large_file_size = 64          files of this many MiB or more are streamed from disk
+//  line_numbers = hybrid         off, absolute, relative or hybrid
+//  soft_wrap = true              long lines continue on the next rows instead of scrolling
+//  tab_width = 4                 columns between tab stops, 8 if not set
+//  indent_style = spaces         what Tab inserts, tabs or spaces
+//  indent_width = 2              columns of one indent level with spaces, 4 if not set
+//  detect_indent = false         keep the style above instead of following the file
+//  auto_indent = false           new lines start at column 0 instead of the indentation above
+//  indent_after = {[(:           indent one level more after a line ending in one of these
+//  dedent_on = }])               go back one level when one of these starts a line
+//
+//global only:
+//  clipboard_command = wl-copy   every copy is piped to this as well as sent to the terminal
+//  osc52_max_size = 74994        bigger copies skip the terminal clipboard, 0 never uses it
+//  theme = onedark               colors from $XDG_CONFIG_HOME/mim/themes/onedark
+//  color_depth = 256             16, 256 or truecolor, detected from the terminal if not set
+#[derive(Default)]
+pub struct Config {
+    global: HashMap<String, String>,
+    filetypes: HashMap<String, HashMap<String, String>>,
+}
+
+const MIB: u64 = 1024 * 1024;
+
+//options resolved for one file
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone)]
+pub struct FileSettings {
+    //always end the file with a newline on save, even if it was loaded without one
+    pub insert_final_newline: bool,
+    //in bytes, bigger files are indexed in the background and read line by line
+    pub large_file_size: u64,
+    pub line_numbers: LineNumbers,
+    pub soft_wrap: bool,
+    pub tab_width: usize,
+    pub indentation: Indentation,
+    //the indentation of the file content wins over `indentation`
+    pub detect_indent: bool,
+    pub auto_indent: bool,
+    //the defaults of these depend on the file type
+    pub indent_after: String,
+    pub dedent_on: String,
+}
+
+impl Default for FileSettings {
+    fn default() -> Self {
+        Self {
+            insert_final_newline: false,
+            large_file_size: 64 * MIB,
+            line_numbers: LineNumbers::default(),
+            soft_wrap: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            indentation: Indentation::default(),
+            detect_indent: true,
+            auto_indent: true,
+            indent_after: String::new(),
+            dedent_on: String::new(),
+        }
+    }
+}
+
+//the terminal clipboard is set with OSC 52, many terminals take at most
+//100000 bytes of base64 which holds this many bytes
+const OSC52_MAX_SIZE: usize = 74_994;
+
+#[derive(Clone)]
+pub struct ClipboardSettings {
+    pub command: Option<String>,
+    //in bytes of copied text
+    pub osc52_max_size: usize,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            command: None,
+            osc52_max_size: OSC52_MAX_SIZE,
+        }
+    }
+}
+
+impl Config {
+    pub fn config_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("mim"))
+    }
+
+    //$XDG_STATE_HOME/mim or ~/.local/state/mim, for data that isn't config but should persist
+    pub fn state_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+            })
+            .map(|dir| dir.join("mim"))
+    }
+
+    //a missing config file is not an error
+    pub fn load() -> Result<Self, Error> {
+        let Some(path) = Self::config_dir().map(|dir| dir.join("config")) else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)?;
+        Self::parse(&data)
+    }
+
+    fn parse(data: &str) -> Result<Self, Error> {
+        let mut config = Self::default();
+        let mut section: Option<String> = None;
+        for (index, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = Some(name.trim().to_ascii_lowercase());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "config line {}: expected `key = value`",
+                        index.saturating_add(1)
+                    ),
+                ));
+            };
+            let values = match section.as_ref() {
+                Some(name) => config.filetypes.entry(name.clone()).or_default(),
+                None => &mut config.global,
+            };
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Ok(config)
+    }
+
+    //filetype section first, then the global options
+    pub fn get(&self, file_type: FileType, key: &str) -> Option<&str> {
+        self.filetypes
+            .get(&file_type.to_string())
+            .and_then(|values| values.get(key))
+            .or_else(|| self.global.get(key))
+            .map(String::as_str)
+    }
+
+    pub fn get_bool(&self, file_type: FileType, key: &str) -> Option<bool> {
+        match self.get(file_type, key)? {
+            "true" | "yes" | "on" => Some(true),
+            "false" | "no" | "off" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn get_parsed<T: FromStr>(&self, file_type: FileType, key: &str) -> Option<T> {
+        self.get(file_type, key)?.parse().ok()
+    }
+
+    pub fn clipboard_settings(&self) -> ClipboardSettings {
+        let default = ClipboardSettings::default();
+        ClipboardSettings {
+            command: self
+                .global
+                .get("clipboard_command")
+                .filter(|command| !command.is_empty())
+                .cloned(),
+            osc52_max_size: self
+                .global
+                .get("osc52_max_size")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(default.osc52_max_size),
+        }
+    }
+
+    pub fn theme_name(&self) -> Option<&str> {
+        self.global
+            .get("theme")
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    pub fn color_depth(&self) -> Option<ColorDepth> {
+        self.global.get("color_depth")?.parse().ok()
+    }
+
+    pub fn file_settings(&self, file_type: FileType) -> FileSettings {
+        let default = FileSettings::default();
+        FileSettings {
+            insert_final_newline: self
+                .get_bool(file_type, "insert_final_newline")
+                .unwrap_or(default.insert_final_newline),
+            large_file_size: self
+                .get_parsed::<u64>(file_type, "large_file_size")
+                .map_or(default.large_file_size, |size| size.saturating_mul(MIB)),
+            line_numbers: self
+                .get_parsed(file_type, "line_numbers")
+                .unwrap_or(default.line_numbers),
+            soft_wrap: self
+                .get_bool(file_type, "soft_wrap")
+                .unwrap_or(default.soft_wrap),
+            tab_width: self
+                .get_parsed::<usize>(file_type, "tab_width")
+                .filter(|width| *width > 0)
+                .unwrap_or(default.tab_width),
+            indentation: Indentation {
+                style: self
+                    .get_parsed(file_type, "indent_style")
+                    .unwrap_or(default.indentation.style),
+                width: self
+                    .get_parsed::<usize>(file_type, "indent_width")
+                    .filter(|width| *width > 0)
+                    .unwrap_or(default.indentation.width),
+            },
+            detect_indent: self
+                .get_bool(file_type, "detect_indent")
+                .unwrap_or(default.detect_indent),
+            auto_indent: self
+                .get_bool(file_type, "auto_indent")
+                .unwrap_or(default.auto_indent),
+            indent_after: self
+                .get(file_type, "indent_after")
+                .unwrap_or(file_type.indent_after())
+                .to_string(),
+            dedent_on: self
+                .get(file_type, "dedent_on")
+                .unwrap_or(file_type.dedent_on())
+                .to_string(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::path::Path;
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum FileType {
+    Rust,
+    C,
+    Python,
+    Json,
+    Toml,
+    Markdown,
+    #[default]
+    Text,
+}
+
+impl FileType {
+    pub fn from_filename(filename: &str) -> Self {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("rs") => Self::Rust,
+            Some("c" | "h") => Self::C,
+            Some("py") => Self::Python,
+            Some("json") => Self::Json,
+            Some("toml") => Self::Toml,
+            Some("md" | "markdown") => Self::Markdown,
+            _ => Self::Text,
+        }
+    }
+
+    //characters at the end of a line that indent the next one
+    pub fn indent_after(self) -> &'static str {
+        match self {
+            Self::Rust | Self::C | Self::Json => "{[(",
+            Self::Python => "{[(:",
+            Self::Toml => "{[",
+            Self::Markdown | Self::Text => "",
+        }
+    }
+
+    //characters typed on a line of only indentation that take it back one level
+    pub fn dedent_on(self) -> &'static str {
+        match self {
+            Self::Rust | Self::C | Self::Json | Self::Python => "}])",
+            Self::Toml => "}]",
+            Self::Markdown | Self::Text => "",
+        }
+    }
+}
+
+//also the section name used in the config file
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Rust => "rust",
+            Self::C => "c",
+            Self::Python => "python",
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Markdown => "markdown",
+            Self::Text => "text",
+        };
+        write!(f, "{name}")
+    }
+}
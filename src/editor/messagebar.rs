@@ -1,5 +1,6 @@
 use super::{
-    terminal::{Size, Terminal},
+    terminal::{Position, Size, Terminal},
+    theme::theme,
     uicomponent::UIComponent,
 };
 use std::io::Error;
@@ -31,9 +32,9 @@ impl UIComponent for MessageBar {
         self.size = size;
     }
 
-    fn draw(&self, y_position: usize) -> Result<(), Error> {
+    fn draw(&self, origin: Position) -> Result<(), Error> {
         let msg = self.message.as_deref().unwrap_or_default();
-        Terminal::print_row(y_position, msg)?;
+        Terminal::print_styled_at(origin, self.size.width, theme().message_bar, msg)?;
         Ok(())
     }
 }
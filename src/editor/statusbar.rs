@@ -1,5 +1,6 @@
 use super::{
-    terminal::{Size, Terminal},
+    terminal::{Position, Size, Terminal},
+    theme::theme,
     uicomponent::UIComponent,
     view::location::Location,
     DocumentStatus,
@@ -9,6 +10,8 @@ use std::io::Error;
 #[derive(Default)]
 pub struct StatusBar {
     document_status: DocumentStatus,
+    //the status line of the focused pane stands out
+    focused: bool,
     redraw: bool,
     size: Size,
 }
@@ -21,6 +24,13 @@ impl StatusBar {
         self.document_status = new_status;
         self.mark_redraw(true);
     }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        if focused != self.focused {
+            self.focused = focused;
+            self.mark_redraw(true);
+        }
+    }
 }
 
 impl UIComponent for StatusBar {
@@ -34,7 +44,7 @@ impl UIComponent for StatusBar {
     fn set_size(&mut self, size: Size) {
         self.size = size;
     }
-    fn draw(&self, start_y: usize) -> Result<(), Error> {
+    fn draw(&self, origin: Position) -> Result<(), Error> {
         let filename = self
             .document_status
             .filename
@@ -43,15 +53,49 @@ impl UIComponent for StatusBar {
 
         let Location { x, y } = self.document_status.curr_location;
 
-        #[allow(clippy::arithmetic_side_effects)]
-        let right_section = format!("{}:{}", y + 1, x + 1);
+        let line_ending = if self.document_status.mixed_line_endings {
+            format!("{} (mixed)", self.document_status.line_ending)
+        } else {
+            self.document_status.line_ending.to_string()
+        };
 
-        let left_section = if self.document_status.is_modified {
-            format!("{} | {}", filename, "[+]")
+        //line:grapheme, with the display column after a dash where it differs
+        let display_column = self.document_status.display_column;
+        #[allow(clippy::arithmetic_side_effects)]
+        let column = if display_column == x {
+            format!("{}", x + 1)
+        } else {
+            format!("{}-{}", x + 1, display_column + 1)
+        };
+        #[allow(clippy::arithmetic_side_effects)]
+        let right_section = if let Some(offset) = self.document_status.byte_offset {
+            format!("hex | 0x{offset:08x}")
         } else {
-            filename.to_string()
+            format!(
+                "{} | {line_ending} | {}:{column}",
+                self.document_status.encoding,
+                y + 1,
+            )
         };
 
+        let mut left_sections = vec![filename.to_string()];
+        if self.document_status.byte_offset.is_none() {
+            let total_lines = self.document_status.total_lines;
+            let more = if self.document_status.is_indexing {
+                "+"
+            } else {
+                ""
+            };
+            left_sections.push(format!("{total_lines}{more} lines"));
+        }
+        if let Some(percent) = self.document_status.load_progress {
+            left_sections.push(format!("loading {percent}%"));
+        }
+        if self.document_status.is_modified {
+            left_sections.push("[+]".to_string());
+        }
+        let left_section = left_sections.join(" | ");
+
         #[allow(clippy::integer_division)]
         let right_width = self.size.width / 2;
         let left_width = self.size.width.saturating_sub(right_width); // to handle odd width
@@ -69,7 +113,12 @@ impl UIComponent for StatusBar {
             empty = ""
         );
 
-        Terminal::print_inverted_row(start_y, &status_line).unwrap();
+        let style = if self.focused {
+            theme().status_bar
+        } else {
+            theme().status_bar_inactive
+        };
+        Terminal::print_styled_at(origin, self.size.width, style, &status_line)?;
         Ok(())
     }
 }
@@ -1,14 +1,21 @@
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     queue,
-    style::{Attribute, Print, SetAttribute},
+    style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
     terminal::{
         disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
         LeaveAlternateScreen, SetTitle,
     },
     Command,
 };
+use std::fmt;
 use std::io::{stdout, Error, Write};
+use unicode_width::UnicodeWidthChar;
+
+pub mod style;
+
+use style::Style;
+
 #[derive(Default, Copy, Clone)]
 pub struct Size {
     pub width: usize,
@@ -29,6 +36,20 @@ impl Position {
         }
     }
 }
+//OSC 52, asks the terminal to put base64 encoded text on the system clipboard
+struct SetClipboard<'a>(&'a str);
+
+impl Command for SetClipboard<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "\x1b]52;c;{}\x07", self.0)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
 pub struct Terminal;
 
 impl Terminal {
@@ -51,10 +72,6 @@ impl Terminal {
         Ok(())
     }
 
-    pub fn clear_line() -> Result<(), Error> {
-        Self::queue_command(Clear(ClearType::CurrentLine))?;
-        Ok(())
-    }
     pub fn show_caret() -> Result<(), Error> {
         Self::queue_command(Show)?;
         Ok(())
@@ -103,29 +120,88 @@ impl Terminal {
         Ok(())
     }
 
-    pub fn print_row(row: usize, line: &str) -> Result<(), Error> {
-        Self::move_caret(Position { col: 0, row })?;
-        Self::clear_line()?;
-        Self::print(line)?;
-        Ok(())
+    //as much of `line` as fits into `width` columns, and the columns it takes
+    fn fit(line: &str, width: usize) -> (String, usize) {
+        let mut fitted = String::with_capacity(line.len());
+        let mut used: usize = 0;
+        for char in line.chars() {
+            let char_width = char.width().unwrap_or(0);
+            if used.saturating_add(char_width) > width {
+                break;
+            }
+            used = used.saturating_add(char_width);
+            fitted.push(char);
+        }
+        (fitted, used)
     }
 
-    pub fn print_inverted_row(row: usize, line: &str) -> Result<(), Error> {
-        Self::invert_color()?;
-        Self::print_row(row, line)?;
-        Self::reset_color()?;
+    //prints `spans` into `width` columns starting at `origin`, cut or padded with blanks.
+    //every span is drawn in its style on top of `base`, the blanks in `base`.
+    //the rest of the row is left alone, for panes next to each other
+    pub fn print_styled_row(
+        origin: Position,
+        width: usize,
+        base: Style,
+        spans: &[(Style, &str)],
+    ) -> Result<(), Error> {
+        Self::move_caret(origin)?;
+        let mut used: usize = 0;
+        for (style, text) in spans {
+            let (fitted, span_width) = Self::fit(text, width.saturating_sub(used));
+            Self::set_style(base.patch(*style))?;
+            Self::print(&fitted)?;
+            used = used.saturating_add(span_width);
+            if fitted.len() < text.len() {
+                break;
+            }
+        }
+        Self::set_style(base)?;
+        Self::print(&" ".repeat(width.saturating_sub(used)))?;
+        Self::reset_style()?;
         Ok(())
     }
 
-    pub fn invert_color() -> Result<(), Error> {
-        Self::queue_command(SetAttribute(Attribute::Reverse))?;
+    pub fn print_styled_at(
+        origin: Position,
+        width: usize,
+        style: Style,
+        line: &str,
+    ) -> Result<(), Error> {
+        Self::print_styled_row(origin, width, style, &[(Style::default(), line)])
+    }
+
+    pub fn set_style(style: Style) -> Result<(), Error> {
+        Self::reset_style()?;
+        if let Some(color) = style.fg {
+            Self::queue_command(SetForegroundColor(color.to_terminal()))?;
+        }
+        if let Some(color) = style.bg {
+            Self::queue_command(SetBackgroundColor(color.to_terminal()))?;
+        }
+        for (enabled, attribute) in [
+            (style.bold, Attribute::Bold),
+            (style.italic, Attribute::Italic),
+            (style.underline, Attribute::Underlined),
+            (style.reverse, Attribute::Reverse),
+        ] {
+            if enabled {
+                Self::queue_command(SetAttribute(attribute))?;
+            }
+        }
         Ok(())
     }
-    pub fn reset_color() -> Result<(), Error> {
+
+    //colors and attributes
+    pub fn reset_style() -> Result<(), Error> {
         Self::queue_command(SetAttribute(Attribute::Reset))?;
         Ok(())
     }
 
+    pub fn set_clipboard(base64: &str) -> Result<(), Error> {
+        Self::queue_command(SetClipboard(base64))?;
+        Ok(())
+    }
+
     pub fn set_title(title: &str) -> Result<(), Error> {
         Self::queue_command(SetTitle(title))?;
         Ok(())
@@ -0,0 +1,335 @@
+use crossterm::style::Color as TerminalColor;
+use std::str::FromStr;
+
+//how many colors the terminal can show
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    #[default]
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    //$COLORTERM is set by terminals with 24 bit colors, $TERM names 256 color ones
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+}
+
+impl FromStr for ColorDepth {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "16" => Ok(Self::Ansi16),
+            "256" => Ok(Self::Ansi256),
+            "truecolor" | "24bit" => Ok(Self::TrueColor),
+            _ => Err(format!("unknown color depth `{value}`")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    //the 16 colors of the terminal palette, 0-7 normal and 8-15 bright
+    Ansi(u8),
+    //the 256 color palette
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+const NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+//what xterm shows for the 16 palette colors
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+//levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .into_iter()
+        .map(|(one, other)| u32::from(one.abs_diff(other)))
+        .map(|diff| diff.saturating_mul(diff))
+        .sum()
+}
+
+//index of the cube level closest to `value`
+fn cube_index(value: u8) -> u8 {
+    (0..6)
+        .min_by_key(|&index| CUBE_LEVELS[usize::from(index)].abs_diff(value))
+        .unwrap_or(0)
+}
+
+impl Color {
+    //#rrggbb, a number of the 256 color palette or a name like `red` or `bright_blue`
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |range| {
+                hex.get(range)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            if hex.len() != 6 {
+                return None;
+            }
+            return Some(Self::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+        }
+        if let Ok(index) = value.parse::<u8>() {
+            return Some(if index < 16 {
+                Self::Ansi(index)
+            } else {
+                Self::Indexed(index)
+            });
+        }
+        let value = match value {
+            "grey" | "gray" => "bright_black",
+            _ => value,
+        };
+        NAMES
+            .iter()
+            .position(|name| *name == value)
+            .and_then(|index| u8::try_from(index).ok())
+            .map(Self::Ansi)
+    }
+
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Self::Rgb(r, g, b) => (r, g, b),
+            Self::Ansi(index) => ANSI_RGB[usize::from(index % 16)],
+            Self::Indexed(index @ 0..=15) => ANSI_RGB[usize::from(index)],
+            Self::Indexed(index @ 232..=255) => {
+                let level = index
+                    .saturating_sub(232)
+                    .saturating_mul(10)
+                    .saturating_add(8);
+                (level, level, level)
+            }
+            Self::Indexed(index) => {
+                let index = index.saturating_sub(16);
+                let level = |index: u8| CUBE_LEVELS[usize::from(index % 6)];
+                #[allow(clippy::integer_division)]
+                (level(index / 36), level(index / 6), level(index))
+            }
+        }
+    }
+
+    //the closest color the terminal can show
+    pub fn fit(self, depth: ColorDepth) -> Self {
+        match (self, depth) {
+            (Self::Ansi(_), _)
+            | (_, ColorDepth::TrueColor)
+            | (Self::Indexed(_), ColorDepth::Ansi256) => self,
+            (Self::Rgb(r, g, b), ColorDepth::Ansi256) => {
+                let cube = 16_u8
+                    .saturating_add(cube_index(r).saturating_mul(36))
+                    .saturating_add(cube_index(g).saturating_mul(6))
+                    .saturating_add(cube_index(b));
+                //the grey ramp goes from 8 to 238 in steps of 10
+                let sum = u16::from(r)
+                    .saturating_add(u16::from(g))
+                    .saturating_add(u16::from(b));
+                #[allow(clippy::integer_division)]
+                let step = (sum / 3).saturating_sub(8) / 10;
+                let grey = 232_u8.saturating_add(u8::try_from(step.min(23)).unwrap_or(23));
+                [cube, grey]
+                    .into_iter()
+                    .map(Self::Indexed)
+                    .min_by_key(|color| distance(color.rgb(), self.rgb()))
+                    .unwrap_or(self)
+            }
+            (_, ColorDepth::Ansi16) => (0..16)
+                .map(Self::Ansi)
+                .min_by_key(|color| distance(color.rgb(), self.rgb()))
+                .unwrap_or(self),
+        }
+    }
+
+    pub fn to_terminal(self) -> TerminalColor {
+        match self {
+            Self::Ansi(index) => match index {
+                0 => TerminalColor::Black,
+                1 => TerminalColor::DarkRed,
+                2 => TerminalColor::DarkGreen,
+                3 => TerminalColor::DarkYellow,
+                4 => TerminalColor::DarkBlue,
+                5 => TerminalColor::DarkMagenta,
+                6 => TerminalColor::DarkCyan,
+                7 => TerminalColor::Grey,
+                8 => TerminalColor::DarkGrey,
+                9 => TerminalColor::Red,
+                10 => TerminalColor::Green,
+                11 => TerminalColor::Yellow,
+                12 => TerminalColor::Blue,
+                13 => TerminalColor::Magenta,
+                14 => TerminalColor::Cyan,
+                _ => TerminalColor::White,
+            },
+            Self::Indexed(index) => TerminalColor::AnsiValue(index),
+            Self::Rgb(r, g, b) => TerminalColor::Rgb { r, g, b },
+        }
+    }
+}
+
+//None keeps the color of the terminal, or of the style below when patched
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+impl Style {
+    pub const fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            bg: None,
+            bold: false,
+            italic: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+
+    //`other` drawn on top of this style
+    pub fn patch(self, other: Self) -> Self {
+        Self {
+            fg: other.fg.or(self.fg),
+            bg: other.bg.or(self.bg),
+            bold: self.bold || other.bold,
+            italic: self.italic || other.italic,
+            underline: self.underline || other.underline,
+            reverse: self.reverse || other.reverse,
+        }
+    }
+
+    pub fn fit(self, depth: ColorDepth) -> Self {
+        Self {
+            fg: self.fg.map(|color| color.fit(depth)),
+            bg: self.bg.map(|color| color.fit(depth)),
+            ..self
+        }
+    }
+
+    //words like `fg=#282c34 bg=blue bold underline`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut style = Self::default();
+        for word in value.split_whitespace() {
+            let color = |color: &str| Color::parse(color).ok_or(format!("unknown color `{color}`"));
+            match word.split_once('=') {
+                Some(("fg", color_name)) => style.fg = Some(color(color_name)?),
+                Some(("bg", color_name)) => style.bg = Some(color(color_name)?),
+                _ => match word {
+                    "bold" => style.bold = true,
+                    "italic" => style.italic = true,
+                    "underline" => style.underline = true,
+                    "reverse" => style.reverse = true,
+                    _ => return Err(format!("unknown style `{word}`")),
+                },
+            }
+        }
+        Ok(style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_the_terminal_can_show_are_kept() {
+        let rgb = Color::Rgb(1, 2, 3);
+        assert_eq!(rgb.fit(ColorDepth::TrueColor), rgb);
+        assert_eq!(
+            Color::Indexed(100).fit(ColorDepth::Ansi256),
+            Color::Indexed(100)
+        );
+        for depth in [
+            ColorDepth::Ansi16,
+            ColorDepth::Ansi256,
+            ColorDepth::TrueColor,
+        ] {
+            assert_eq!(Color::Ansi(3).fit(depth), Color::Ansi(3));
+        }
+    }
+
+    #[test]
+    fn rgb_fits_the_cube_or_the_grey_ramp() {
+        let fit = |r, g, b| Color::Rgb(r, g, b).fit(ColorDepth::Ansi256);
+        assert_eq!(fit(95, 135, 175), Color::Indexed(67));
+        assert_eq!(fit(255, 0, 0), Color::Indexed(196));
+        assert_eq!(fit(0, 0, 0), Color::Indexed(16));
+        assert_eq!(fit(128, 128, 128), Color::Indexed(244));
+        assert_eq!(fit(240, 240, 240), Color::Indexed(255));
+    }
+
+    #[test]
+    fn sixteen_colors_take_the_closest_palette_color() {
+        let fit = |color: Color| color.fit(ColorDepth::Ansi16);
+        assert_eq!(fit(Color::Rgb(250, 5, 5)), Color::Ansi(9));
+        assert_eq!(fit(Color::Rgb(200, 10, 0)), Color::Ansi(1));
+        assert_eq!(fit(Color::Indexed(196)), Color::Ansi(9));
+        assert_eq!(fit(Color::Indexed(244)), Color::Ansi(8));
+        assert_eq!(fit(Color::Rgb(10, 10, 10)), Color::Ansi(0));
+    }
+
+    #[test]
+    fn style_fits_both_colors() {
+        let style = Style {
+            fg: Some(Color::Rgb(255, 0, 0)),
+            bg: Some(Color::Rgb(0, 0, 0)),
+            bold: true,
+            ..Style::default()
+        };
+        let fitted = style.fit(ColorDepth::Ansi16);
+        assert_eq!(fitted.fg, Some(Color::Ansi(9)));
+        assert_eq!(fitted.bg, Some(Color::Ansi(0)));
+        assert!(fitted.bold);
+    }
+}
@@ -0,0 +1,167 @@
+use super::config::Config;
+use super::terminal::style::{Color, ColorDepth, Style};
+use super::view::highlight::TokenKind;
+use std::io::{Error, ErrorKind};
+use std::sync::OnceLock;
+
+//theme file: $XDG_CONFIG_HOME/mim/themes/<name>, picked with `theme = <name>` in the config
+//
+//  # comment
+//  text = fg=#abb2bf bg=#282c34
+//  keyword = fg=magenta bold
+//  status_bar = reverse
+//
+//colors are #rrggbb, 0-255 or one of the 16 names like `red` or `bright_red`,
+//attributes are bold, italic, underline and reverse.
+//everything not in the file keeps the look of the default theme.
+//`color_depth = 16`, `256` or `truecolor` in the config overrides what the terminal reports
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub text: Style,
+    pub status_bar: Style,
+    //status lines of the panes without focus
+    pub status_bar_inactive: Style,
+    pub message_bar: Style,
+    pub selection: Style,
+    pub search_match: Style,
+    pub line_number: Style,
+    pub current_line_number: Style,
+    pub keyword: Style,
+    pub string: Style,
+    pub comment: Style,
+    pub number: Style,
+    pub type_name: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: Style::default(),
+            status_bar: Style {
+                reverse: true,
+                ..Style::default()
+            },
+            status_bar_inactive: Style::default(),
+            message_bar: Style::default(),
+            selection: Style {
+                reverse: true,
+                ..Style::default()
+            },
+            search_match: Style {
+                bg: Some(Color::Ansi(3)),
+                ..Style::fg(Color::Ansi(0))
+            },
+            line_number: Style::fg(Color::Ansi(8)),
+            current_line_number: Style {
+                bold: true,
+                ..Style::default()
+            },
+            keyword: Style::fg(Color::Ansi(13)),
+            string: Style::fg(Color::Ansi(10)),
+            comment: Style::fg(Color::Ansi(8)),
+            number: Style::fg(Color::Ansi(14)),
+            type_name: Style::fg(Color::Ansi(11)),
+        }
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+//the theme everything is drawn with, the default one until `install` is called
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+impl Theme {
+    pub fn token(&self, kind: TokenKind) -> Style {
+        match kind {
+            TokenKind::Keyword => self.keyword,
+            TokenKind::String => self.string,
+            TokenKind::Comment => self.comment,
+            TokenKind::Number => self.number,
+            TokenKind::Type => self.type_name,
+        }
+    }
+
+    fn style_mut(&mut self, key: &str) -> Option<&mut Style> {
+        Some(match key {
+            "text" => &mut self.text,
+            "status_bar" => &mut self.status_bar,
+            "status_bar_inactive" => &mut self.status_bar_inactive,
+            "message_bar" => &mut self.message_bar,
+            "selection" => &mut self.selection,
+            "search_match" => &mut self.search_match,
+            "line_number" => &mut self.line_number,
+            "current_line_number" => &mut self.current_line_number,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "comment" => &mut self.comment,
+            "number" => &mut self.number,
+            "type" => &mut self.type_name,
+            _ => return None,
+        })
+    }
+
+    fn parse(data: &str) -> Result<Self, Error> {
+        let mut theme = Self::default();
+        for (index, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("theme line {}: {message}", index.saturating_add(1)),
+                )
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected `key = value`".to_string()));
+            };
+            let key = key.trim();
+            let style = theme
+                .style_mut(key)
+                .ok_or_else(|| error(format!("unknown key `{key}`")))?;
+            *style = Style::parse(value).map_err(error)?;
+        }
+        Ok(theme)
+    }
+
+    //the theme named in the config with its colors brought down to what the terminal can show
+    pub fn load(config: &Config) -> Result<Self, Error> {
+        let theme = match config.theme_name() {
+            None | Some("default") => Self::default(),
+            Some(name) => {
+                let path = Config::config_dir()
+                    .map(|dir| dir.join("themes").join(name))
+                    .ok_or_else(|| Error::new(ErrorKind::NotFound, "no config directory"))?;
+                Self::parse(&std::fs::read_to_string(path)?)?
+            }
+        };
+        Ok(theme.fit(config.color_depth().unwrap_or_else(ColorDepth::detect)))
+    }
+
+    fn fit(self, depth: ColorDepth) -> Self {
+        let fit = |style: Style| style.fit(depth);
+        Self {
+            text: fit(self.text),
+            status_bar: fit(self.status_bar),
+            status_bar_inactive: fit(self.status_bar_inactive),
+            message_bar: fit(self.message_bar),
+            selection: fit(self.selection),
+            search_match: fit(self.search_match),
+            line_number: fit(self.line_number),
+            current_line_number: fit(self.current_line_number),
+            keyword: fit(self.keyword),
+            string: fit(self.string),
+            comment: fit(self.comment),
+            number: fit(self.number),
+            type_name: fit(self.type_name),
+        }
+    }
+
+    //only the first theme counts, it is installed once at startup
+    pub fn install(self) {
+        let _ = THEME.set(self);
+    }
+}
@@ -1,20 +1,21 @@
-use super::terminal::Size;
+use super::terminal::{Position, Size};
 use std::io::Error;
 pub trait UIComponent {
     fn mark_redraw(&mut self, redraw: bool);
     fn needs_redraw(&self) -> bool;
 
     fn set_size(&mut self, size: Size);
-    fn draw(&self, y_position: usize) -> Result<(), Error>;
+    //`origin` is the top left corner of the component on screen
+    fn draw(&self, origin: Position) -> Result<(), Error>;
 
     fn resize(&mut self, size: Size) {
         self.set_size(size);
         self.mark_redraw(true);
     }
 
-    fn render(&mut self, start_y: usize) {
+    fn render(&mut self, origin: Position) {
         if self.needs_redraw() {
-            match self.draw(start_y) {
+            match self.draw(origin) {
                 Ok(()) => self.mark_redraw(false),
                 Err(err) => {
                     #[cfg(debug_assertions)]
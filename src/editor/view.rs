@@ -1,19 +1,41 @@
 //one two three four five six seven eight nine ten eleven twelve thirteen fourteen fifteen sixteen seventeen eighteen nineteen twenty twenty-one twenty-two twenty-three twenty-four twenty-five twenty-six twenty-seven twenty-eight twenty-nine thirty thirty-one thirty-two thirty-three thirty-four thirty-five thirty-six thirty-seven thirty-eight thirty-nine forty forty-one forty-two forty-three forty-four forty-five forty-six forty-seven forty-eight forty-nine fifty fifty-one fifty-two fifty-three fifty-four fifty-five
 use super::{
     command::{Edit, Move},
-    terminal::{Position, Size, Terminal},
+    config::Config,
+    terminal::{style::Style, Position, Size, Terminal},
+    theme::theme,
     uicomponent::UIComponent,
     view::location::Location,
     DocumentStatus,
 };
+use std::cell::{Ref, RefCell, RefMut};
+use std::cmp::Ordering;
 use std::io::Error;
+use std::ops::Range;
+use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
 
-mod buffer;
+pub mod buffer;
+pub mod encoding;
+pub mod gutter;
+mod hex;
+pub mod highlight;
+pub mod indent;
 pub mod line;
+pub mod lineending;
 pub mod location;
+pub mod search;
 
 use buffer::Buffer;
-use line::Line;
+
+//buffers are shared by every pane that shows them
+pub type SharedBuffer = Rc<RefCell<Buffer>>;
+use hex::{HexCursor, BYTES_PER_ROW};
+use highlight::TokenKind;
+use indent::IndentStyle;
+use line::{Line, WrappedRow};
+use lineending::LineEnding;
+use search::{Replacement, SearchInfo, SearchOptions};
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -22,13 +44,30 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 //y->current line in the text
 //x->current grapheme in the text
 //
-#[derive(Default)]
+//binary buffers use `hex_cursor` instead of `location`
+//and scroll_offset.row counts rows of the hex view
+//
+//with soft wrap, scroll_offset.row is the line at the top and `wrap_offset`
+//counts its rows above the view, scroll_offset.col stays 0
+//
+//the buffer may be shown and edited by other views too,
+//`seen_revision` tells if it changed since this view last looked
+//
+//`search` is set while the search prompt is open, its matches are highlighted
+//
+//the selection runs from `selection_anchor` to `location`, either may come first
+#[derive(Default, Clone)]
 pub struct View {
-    buffer: Buffer,
+    buffer: SharedBuffer,
+    seen_revision: usize,
     redraw: bool,
     size: Size,
     location: Location,
+    hex_cursor: HexCursor,
     scroll_offset: Position,
+    wrap_offset: usize,
+    search: Option<SearchInfo>,
+    selection_anchor: Option<Location>,
 }
 
 impl View {
@@ -48,27 +87,273 @@ impl View {
         self.size.height
     }
 
-    fn render_line(at: usize, line: &str) -> Result<(), Error> {
-        Terminal::print_row(at, line)?;
+    //line numbers left of the text, the hex view has its own offsets
+    fn gutter_width(&self) -> usize {
+        let buffer = self.buffer();
+        if buffer.is_binary() {
+            return 0;
+        }
+        buffer.settings.line_numbers.width(buffer.line_count())
+    }
+
+    //columns left for the text next to the gutter
+    fn text_width(&self) -> usize {
+        self.size.width.saturating_sub(self.gutter_width())
+    }
+
+    //draws the graphemes in `range` of `line` again in `style`, over the line already on screen.
+    //`columns` are the columns of the line shown starting at `at`
+    fn highlight(
+        at: Position,
+        line: &Line,
+        columns: &Range<usize>,
+        range: Range<usize>,
+        style: Style,
+    ) -> Result<(), Error> {
+        let Range {
+            start: left,
+            end: right,
+        } = *columns;
+        let start = line.width_until(range.start).max(left);
+        let end = line.width_until(range.end).min(right);
+        if start >= end {
+            return Ok(());
+        }
+        let origin = Position {
+            row: at.row,
+            col: at.col.saturating_add(start.saturating_sub(left)),
+        };
+        Terminal::print_styled_at(
+            origin,
+            end.saturating_sub(start),
+            theme().text.patch(style),
+            &line.get_graphemes(start..end),
+        )
+    }
+
+    fn highlight_matches(
+        &self,
+        at: Position,
+        line: &Line,
+        columns: &Range<usize>,
+    ) -> Result<(), Error> {
+        let Some(pattern) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.pattern.as_ref())
+        else {
+            return Ok(());
+        };
+        for found in line.find_all(pattern) {
+            Self::highlight(at, line, columns, found, theme().search_match)?;
+        }
+        Ok(())
+    }
+
+    //a selected line break shows as one selected blank after the line
+    fn highlight_selection(
+        &self,
+        at: Position,
+        y: usize,
+        line: &Line,
+        columns: &Range<usize>,
+    ) -> Result<(), Error> {
+        let Some((start, end)) = self.selection() else {
+            return Ok(());
+        };
+        if y < start.y || y > end.y {
+            return Ok(());
+        }
+        let from = if y == start.y { start.x } else { 0 };
+        let to = if y == end.y {
+            end.x
+        } else {
+            line.grapheme_count()
+        };
+        Self::highlight(at, line, columns, from..to, theme().selection)?;
+        let line_break = line.width();
+        let left = columns.start;
+        if y < end.y && columns.contains(&line_break) {
+            let origin = Position {
+                row: at.row,
+                col: at.col.saturating_add(line_break.saturating_sub(left)),
+            };
+            Terminal::print_styled_at(origin, 1, theme().text.patch(theme().selection), " ")?;
+        }
+        Ok(())
+    }
+
+    //only the columns of the view are drawn over, panes may sit next to it
+    fn render_line(&self, at: Position, line: &str) -> Result<(), Error> {
+        Terminal::print_styled_at(at, self.text_width(), theme().text, line)?;
         Ok(())
     }
 
-    pub fn load(&mut self, file: &str) -> Result<(), Error> {
-        let buffer = Buffer::load(file)?;
+    //right aligned number of line `y`, blank past the end of the buffer
+    fn render_gutter(&self, at: Position, y: Option<usize>) -> Result<(), Error> {
+        let width = self.gutter_width();
+        if width == 0 {
+            return Ok(());
+        }
+        let line_numbers = self.buffer().settings.line_numbers;
+        let (label, style) = match y {
+            Some(y) if y == self.location.y => (
+                line_numbers.label(y, self.location.y),
+                theme().current_line_number,
+            ),
+            Some(y) => (line_numbers.label(y, self.location.y), theme().line_number),
+            None => (String::new(), theme().line_number),
+        };
+        let digits = width.saturating_sub(1);
+        let label = format!("{label:>digits$} ");
+        Terminal::print_styled_at(at, width, theme().text.patch(style), &label)?;
+        Ok(())
+    }
+
+    fn render_spans(
+        &self,
+        at: Position,
+        spans: &[(Option<TokenKind>, String)],
+    ) -> Result<(), Error> {
+        let spans: Vec<_> = spans
+            .iter()
+            .map(|(kind, text)| {
+                let style = kind.map_or(Style::default(), |kind| theme().token(kind));
+                (style, text.as_str())
+            })
+            .collect();
+        Terminal::print_styled_row(at, self.text_width(), theme().text, &spans)?;
+        Ok(())
+    }
+
+    pub fn new(buffer: SharedBuffer) -> Self {
+        let mut view = Self::default();
+        view.set_buffer(buffer);
+        view
+    }
+
+    fn buffer(&self) -> Ref<'_, Buffer> {
+        self.buffer.borrow()
+    }
+
+    fn buffer_mut(&self) -> RefMut<'_, Buffer> {
+        self.buffer.borrow_mut()
+    }
+
+    pub fn shared_buffer(&self) -> &SharedBuffer {
+        &self.buffer
+    }
+
+    //the cursor is remembered in the buffer left behind and restored from the new one
+    pub fn set_buffer(&mut self, buffer: SharedBuffer) {
+        self.buffer_mut().last_location = self.location;
         self.buffer = buffer;
+        let revision = self.buffer().revision();
+        self.seen_revision = revision;
+        self.hex_cursor = HexCursor::default();
+        self.scroll_offset = Position::default();
+        self.wrap_offset = 0;
+        let location = self.buffer().last_location;
+        self.restore_location(location);
+    }
+
+    //keeps the cursor inside the buffer after it was changed elsewhere
+    pub fn sync(&mut self) {
+        let revision = self.buffer().revision();
+        if revision == self.seen_revision {
+            return;
+        }
+        self.seen_revision = revision;
+        if self.buffer().is_binary() {
+            let last = self.buffer().bytes().len().saturating_sub(1);
+            self.hex_cursor.offset = std::cmp::min(self.hex_cursor.offset, last);
+            self.update_hex_scroll_offset();
+        } else {
+            self.restore_location(self.location);
+        }
+        self.mark_redraw(true);
+    }
+
+    //keeps the cursor where it was as far as the new content allows
+    pub fn reload(&mut self, config: &Config) -> Result<(), Error> {
+        self.buffer_mut().reload(config)?;
+        if self.buffer().is_binary() {
+            let last = self.buffer().bytes().len().saturating_sub(1);
+            self.hex_cursor.offset = std::cmp::min(self.hex_cursor.offset, last);
+            self.update_hex_scroll_offset();
+        } else {
+            self.restore_location(self.location);
+        }
+        self.mark_redraw(true);
+        Ok(())
+    }
+
+    pub fn changed_on_disk(&mut self) -> bool {
+        self.buffer_mut().changed_on_disk()
+    }
+
+    pub fn has_new_disk_change(&mut self) -> bool {
+        self.buffer_mut().has_new_disk_change()
+    }
+
+    pub fn ignore_disk_change(&mut self) {
+        self.buffer_mut().ignore_disk_change();
+    }
+
+    pub fn diff_with_disk(&self, config: &Config) -> Result<Option<String>, Error> {
+        self.buffer().diff_with_disk(config)
+    }
+
+    pub fn has_stale_swap(&self) -> bool {
+        self.buffer().has_stale_swap()
+    }
+
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        self.buffer_mut().recover_swap()?;
+        if !self.buffer().is_binary() {
+            self.restore_location(self.location);
+        }
         self.mark_redraw(true);
         Ok(())
     }
 
+    pub fn diff_with_swap(&self) -> Result<Option<String>, Error> {
+        self.buffer().diff_with_swap()
+    }
+
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        self.buffer_mut().discard_swap()
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.buffer().is_binary()
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.buffer().is_streaming()
+    }
+
+    pub fn start_full_load(&mut self) {
+        self.buffer_mut().start_full_load();
+    }
+
+    //lines of a streamed file show up while it is being indexed
+    pub fn tick(&mut self) {
+        if self.buffer().is_indexing() {
+            self.mark_redraw(true);
+        }
+    }
+
     fn insert_char(&mut self, char: char) {
+        self.dedent_for(char);
         //handle enter
         let Location { y, x: _ } = self.location;
 
         //handle None
-        let old_graphemes = self.buffer.lines.get(y).map_or(0, Line::grapheme_count);
+        let old_graphemes = self.buffer().grapheme_count(y);
 
-        self.buffer.insert_char(char, self.location);
-        let new_graphemes = self.buffer.lines.get(y).map_or(0, Line::grapheme_count);
+        self.buffer_mut().insert_char(char, self.location);
+        let new_graphemes = self.buffer().grapheme_count(y);
 
         if old_graphemes != new_graphemes {
             self.handle_move_command(Move::Right);
@@ -85,34 +370,477 @@ impl View {
 
     fn delete(&mut self) {
         //maybe simplify
-        if self.buffer.is_last_line(self.location.y)
-            && self.location.x
-                == self
-                    .buffer
-                    .lines
-                    .get(self.location.y)
-                    .unwrap()
-                    .grapheme_count()
+        if self.buffer().is_last_line(self.location.y)
+            && self.location.x == self.buffer().grapheme_count(self.location.y)
         {
             return;
         }
-        self.buffer.delete(self.location);
+        self.buffer_mut().delete(self.location);
+        self.mark_redraw(true);
+    }
+
+    //deletes what a word motion moves over, joining lines at either end of a line
+    fn delete_word(&mut self) {
+        let start = self.location;
+        self.move_word_right();
+        let end = self.location;
+        self.location = start;
+        if end.y != start.y {
+            self.delete();
+            return;
+        }
+        self.buffer_mut().remove_range(start, end);
+        self.mark_redraw(true);
+    }
+
+    fn delete_word_backward(&mut self) {
+        let end = self.location;
+        self.move_word_left();
+        let start = self.location;
+        if start.y != end.y {
+            self.location = end;
+            self.delete_backward();
+            return;
+        }
+        self.buffer_mut().remove_range(start, end);
+        self.restore_location(start);
+        self.mark_redraw(true);
+    }
+
+    //to the next indent stop
+    fn indent(&mut self) {
+        let Location { x, y } = self.location;
+        let column = self.buffer().line(y).map_or(0, |line| line.width_until(x));
+        let fill = self.buffer().settings.indentation.fill(column);
+        let after = self.buffer_mut().insert_text(self.location, &fill);
+        self.restore_location(after);
+        self.mark_redraw(true);
+    }
+
+    //backspace in the leading spaces of a line removes a whole indent level,
+    //false if the cursor isn't there
+    fn unindent(&mut self) -> bool {
+        let indentation = self.buffer().settings.indentation;
+        let Location { x, y } = self.location;
+        if indentation.style != IndentStyle::Spaces || x == 0 {
+            return false;
+        }
+        let Some(line) = self.buffer().line(y) else {
+            return false;
+        };
+        if !line
+            .to_string()
+            .graphemes(true)
+            .take(x)
+            .all(|grapheme| grapheme == " ")
+        {
+            return false;
+        }
+        let start = Location {
+            x: x.saturating_sub(indentation.unindent(x)),
+            y,
+        };
+        self.buffer_mut().remove_range(start, self.location);
+        self.restore_location(start);
         self.mark_redraw(true);
+        true
     }
 
     fn insert_new_line(&mut self) {
-        self.buffer.insert_new_line(self.location);
-        self.move_down(1);
-        self.move_to_start_of_line();
+        let (indent, closer_indent) = self.new_line_indent();
+        //the closer goes on a line of its own, blanks before it would only push it right
+        if closer_indent.is_some() {
+            let Location { x, y } = self.location;
+            let blanks = self.buffer().line(y).map_or(0, |line| {
+                line.to_string()
+                    .graphemes(true)
+                    .skip(x)
+                    .take_while(|grapheme| is_blank(grapheme))
+                    .count()
+            });
+            let end = Location {
+                x: x.saturating_add(blanks),
+                y,
+            };
+            self.buffer_mut().remove_range(self.location, end);
+        }
+        let after = self
+            .buffer_mut()
+            .insert_text(self.location, &format!("\n{indent}"));
+        if let Some(closer_indent) = closer_indent {
+            self.buffer_mut()
+                .insert_text(after, &format!("\n{closer_indent}"));
+        }
+        self.restore_location(after);
+        self.mark_redraw(true);
+    }
+
+    //indentation of the line Enter starts: the one of the line it is split from,
+    //a level more after an opener. if a closer follows the cursor, it gets a line
+    //of its own with the indentation of the opener's line
+    fn new_line_indent(&self) -> (String, Option<String>) {
+        let buffer = self.buffer();
+        let settings = &buffer.settings;
+        let Location { x, y } = self.location;
+        let Some(line) = buffer.line(y).filter(|_| settings.auto_indent) else {
+            return (String::new(), None);
+        };
+        let text = line.to_string();
+        let graphemes: Vec<&str> = text.graphemes(true).collect();
+        let (before, after) = graphemes.split_at(x.min(graphemes.len()));
+        let indent_len = before
+            .iter()
+            .take_while(|grapheme| is_blank(grapheme))
+            .count();
+        let indent = before[..indent_len].concat();
+        let opens = before
+            .iter()
+            .rfind(|grapheme| !is_blank(grapheme))
+            .is_some_and(|grapheme| settings.indent_after.contains(*grapheme));
+        if !opens {
+            return (indent, None);
+        }
+        let level = settings.indentation.fill(line.width_until(indent_len));
+        let closes = after
+            .iter()
+            .find(|grapheme| !is_blank(grapheme))
+            .is_some_and(|grapheme| settings.dedent_on.contains(*grapheme));
+        (format!("{indent}{level}"), closes.then_some(indent))
+    }
+
+    //a closer typed on a line of only indentation takes it back one level
+    fn dedent_for(&mut self, char: char) {
+        let Location { x, y } = self.location;
+        let remove = {
+            let buffer = self.buffer();
+            let settings = &buffer.settings;
+            let Some(line) = buffer.line(y) else {
+                return;
+            };
+            let text = line.to_string();
+            if !settings.auto_indent
+                || !settings.dedent_on.contains(char)
+                || x == 0
+                || !text.graphemes(true).all(is_blank)
+            {
+                return;
+            }
+            let before: Vec<&str> = text.graphemes(true).take(x).collect();
+            if before.last() == Some(&"\t") {
+                1
+            } else {
+                let spaces = before
+                    .iter()
+                    .rev()
+                    .take_while(|grapheme| **grapheme == " ")
+                    .count();
+                settings
+                    .indentation
+                    .unindent(line.width_until(x))
+                    .min(spaces)
+            }
+        };
+        let start = Location {
+            x: x.saturating_sub(remove),
+            y,
+        };
+        self.buffer_mut().remove_range(start, self.location);
+        self.restore_location(start);
+    }
+
+    fn undo(&mut self) {
+        let location = self.buffer_mut().undo();
+        if let Some(location) = location {
+            self.restore_location(location);
+        }
+    }
+
+    fn redo(&mut self) {
+        let location = self.buffer_mut().redo();
+        if let Some(location) = location {
+            self.restore_location(location);
+        }
+    }
+
+    //jumps elsewhere drop the selection
+    fn restore_location(&mut self, location: Location) {
+        self.selection_anchor = None;
+        self.location = location;
+        self.snap_vertical();
+        self.snap_horizontal();
+        self.update_scroll_offset();
+    }
+
+    //region: selection
+    //ordered ends of the selection, None if nothing is selected
+    fn selection(&self) -> Option<(Location, Location)> {
+        let anchor = self.selection_anchor?;
+        match anchor.cmp(&self.location) {
+            Ordering::Less => Some((anchor, self.location)),
+            Ordering::Greater => Some((self.location, anchor)),
+            Ordering::Equal => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        Some(self.buffer().text_range(start, end))
+    }
+
+    //left uncommitted, false if nothing was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            return false;
+        };
+        self.buffer_mut().remove_range(start, end);
+        self.restore_location(start);
+        self.mark_redraw(true);
+        true
+    }
+
+    pub fn cut(&mut self) -> Option<String> {
+        let text = self.selected_text()?;
+        let before = self.location;
+        self.delete_selection();
+        self.buffer_mut().commit_step(before, self.location);
+        Some(text)
+    }
+
+    //replaces the selection, the cursor ends up after the pasted text
+    pub fn paste(&mut self, text: &str) {
+        if self.buffer().is_binary() {
+            return;
+        }
+        let before = self.location;
+        self.delete_selection();
+        let after = self.buffer_mut().insert_text(self.location, text);
+        self.restore_location(after);
+        self.buffer_mut().commit_step(before, self.location);
+    }
+
+    //moves like `handle_move_command`, the other end of the selection stays put
+    pub fn handle_select_command(&mut self, direction: Move) {
+        if self.buffer().is_binary() {
+            self.handle_move_command(direction);
+            return;
+        }
+        let anchor = self.selection_anchor.unwrap_or(self.location);
+        self.handle_move_command(direction);
+        self.selection_anchor = Some(anchor);
+        self.mark_redraw(true);
+    }
+    //end region: selection
+
+    //region: search
+    pub fn enter_search(&mut self) {
+        self.search = Some(SearchInfo {
+            prev_location: self.location,
+            prev_scroll_offset: self.scroll_offset,
+            prev_wrap_offset: self.wrap_offset,
+            pattern: None,
+            replacement: None,
+        });
+    }
+
+    //the cursor stays at the match
+    pub fn exit_search(&mut self) {
+        self.search = None;
+        self.mark_redraw(true);
+    }
+
+    //back to where the search started
+    pub fn dismiss_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.restore_location(search.prev_location);
+            self.scroll_offset = search.prev_scroll_offset;
+            self.wrap_offset = search.prev_wrap_offset;
+            self.update_scroll_offset();
+        }
+        self.mark_redraw(true);
+    }
+
+    //stays on the current match as long as it still matches the longer query,
+    //an invalid regex leaves the cursor where it is
+    pub fn search(&mut self, query: &str, options: SearchOptions) -> Result<(), regex::Error> {
+        let pattern = if query.is_empty() {
+            None
+        } else {
+            options.compile(query).map(Some)?
+        };
+        let Some(search) = self.search.as_mut() else {
+            return Ok(());
+        };
+        let prev_location = search.prev_location;
+        search.pattern = pattern;
+        self.mark_redraw(true);
+        if query.is_empty() {
+            self.restore_location(prev_location);
+        } else {
+            self.search_from(self.location, true);
+        }
+        Ok(())
+    }
+
+    pub fn search_next(&mut self) {
+        let from = Location {
+            x: self.location.x.saturating_add(1),
+            y: self.location.y,
+        };
+        self.search_from(from, true);
+    }
+
+    pub fn search_previous(&mut self) {
+        self.search_from(self.location, false);
+    }
+
+    fn search_from(&mut self, from: Location, forward: bool) {
+        let Some(pattern) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.pattern.clone())
+        else {
+            return;
+        };
+        let found = if forward {
+            search::find_forward(&self.buffer(), &pattern, from)
+        } else {
+            search::find_backward(&self.buffer(), &pattern, from)
+        };
+        if let Some(location) = found {
+            self.restore_location(location);
+        }
+    }
+    //end region: search
+
+    //region: replace
+    //starts at the match under the cursor, false if there is none
+    pub fn start_replace(&mut self, text: String) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        search.replacement = Some(Replacement {
+            text,
+            stop: self.location,
+            wrapped: false,
+            count: 0,
+        });
+        self.match_at_cursor().is_some()
+    }
+
+    fn match_at_cursor(&self) -> Option<Range<usize>> {
+        let pattern = self.search.as_ref()?.pattern.as_ref()?;
+        let Location { x, y } = self.location;
+        self.buffer()
+            .line(y)?
+            .find_all(pattern)
+            .into_iter()
+            .find(|found| found.start == x)
+    }
+
+    //replaces the match under the cursor and moves to the next one,
+    //false once all matches were visited
+    pub fn replace_match(&mut self) -> bool {
+        let Some(SearchInfo {
+            pattern: Some(pattern),
+            replacement: Some(replacement),
+            ..
+        }) = self.search.as_ref()
+        else {
+            return false;
+        };
+        let (pattern, text) = (pattern.clone(), replacement.text.clone());
+        let Location { x, y } = self.location;
+        let found = self
+            .buffer()
+            .line(y)
+            .and_then(|line| line.expand_match(&pattern, x, &text));
+        let Some((range, expanded)) = found else {
+            return self.skip_match();
+        };
+        let len = expanded.graphemes(true).count();
+        self.buffer_mut().replace(y, range.clone(), &expanded);
+        if let Some(replacement) = self.replacement_mut() {
+            replacement.count = replacement.count.saturating_add(1);
+            //the text before `stop` changed length
+            if replacement.wrapped && replacement.stop.y == y {
+                replacement.stop.x = replacement
+                    .stop
+                    .x
+                    .saturating_add(len)
+                    .saturating_sub(range.len());
+            }
+        }
+        //an empty match would be found again at the same place
+        let skip = usize::from(range.is_empty());
+        self.next_replace_match(Location {
+            x: x.saturating_add(len).saturating_add(skip),
+            y,
+        })
+    }
+
+    pub fn skip_match(&mut self) -> bool {
+        self.next_replace_match(Location {
+            x: self.location.x.saturating_add(1),
+            y: self.location.y,
+        })
+    }
+
+    pub fn replace_all(&mut self) {
+        while self.replace_match() {}
+    }
+
+    fn replacement_mut(&mut self) -> Option<&mut Replacement> {
+        self.search.as_mut()?.replacement.as_mut()
+    }
+
+    fn next_replace_match(&mut self, from: Location) -> bool {
+        let Some(pattern) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.pattern.clone())
+        else {
+            return false;
+        };
+        let found = search::find_forward(&self.buffer(), &pattern, from);
+        let (Some(found), Some(replacement)) = (found, self.replacement_mut()) else {
+            return false;
+        };
+        if found < from {
+            if replacement.wrapped {
+                return false;
+            }
+            replacement.wrapped = true;
+        }
+        if replacement.wrapped && found >= replacement.stop {
+            return false;
+        }
+        self.restore_location(found);
+        true
+    }
+
+    //the replacements made so far become one undo step, returns how many there were
+    pub fn finish_replace(&mut self) -> usize {
+        let Some(search) = self.search.take() else {
+            return 0;
+        };
+        let count = search
+            .replacement
+            .map_or(0, |replacement| replacement.count);
+        if count > 0 {
+            self.buffer_mut()
+                .commit_step(search.prev_location, self.location);
+        }
         self.mark_redraw(true);
+        count
     }
+    //end region: replace
 
     pub fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save_file()?;
+        self.buffer_mut().save_file()?;
         Ok(())
     }
-    pub fn save_as(&mut self, filename: String) -> Result<(), Error> {
-        self.buffer.save_as(filename)?;
+    pub fn save_as(&mut self, filename: String, config: &Config) -> Result<(), Error> {
+        self.buffer_mut().save_as(filename, config)?;
         Ok(())
     }
 
@@ -144,20 +872,12 @@ impl View {
     }
 
     fn move_right(&mut self) {
-        let len = self
-            .buffer
-            .lines
-            .get(self.location.y)
-            .map_or(0, Line::grapheme_count);
+        let len = self.buffer().grapheme_count(self.location.y);
         self.location.x = std::cmp::min(self.location.x.saturating_add(1), len);
     }
 
     fn move_right_y(&mut self) {
-        let len = self
-            .buffer
-            .lines
-            .get(self.location.y)
-            .map_or(0, Line::grapheme_count);
+        let len = self.buffer().grapheme_count(self.location.y);
 
         if self.location.x < len {
             self.location.x = self.location.x.saturating_add(1);
@@ -167,43 +887,343 @@ impl View {
         }
     }
 
+    //word motions go on to the line before or after at either end of a line
+    fn move_word_left(&mut self) {
+        let Location { x, y } = self.location;
+        if x == 0 {
+            if y > 0 {
+                self.move_left_y();
+            }
+            return;
+        }
+        let start = self
+            .buffer()
+            .line(y)
+            .map_or(0, |line| line.word_start_before(x));
+        self.location.x = start;
+    }
+
+    fn move_word_right(&mut self) {
+        let Location { x, y } = self.location;
+        let Some(line) = self.buffer().line(y) else {
+            return;
+        };
+        if x >= line.grapheme_count() {
+            self.move_right_y();
+        } else {
+            self.location.x = line.word_end_after(x);
+        }
+    }
+
     fn move_to_start_of_line(&mut self) {
         self.location.x = 0;
     }
     fn move_to_end_of_line(&mut self) {
         let y = self.location.y;
-        self.location.x = self.buffer.lines.get(y).map_or(0, Line::grapheme_count);
+        let len = self.buffer().grapheme_count(y);
+        self.location.x = len;
     }
 
     // cursor snapping
     fn snap_horizontal(&mut self) {
-        let len = self
-            .buffer
-            .lines
-            .get(self.location.y)
-            .map_or(0, Line::grapheme_count);
+        let len = self.buffer().grapheme_count(self.location.y);
 
         self.location.x = std::cmp::min(len, self.location.x);
     }
 
     fn snap_vertical(&mut self) {
-        self.location.y = std::cmp::min(self.location.y, self.buffer.lines.len());
+        let line_count = self.buffer().line_count();
+        self.location.y = std::cmp::min(self.location.y, line_count);
     }
     //end region: cursor movement
 
+    //region: hex view
+    fn hex_row_count(&self) -> usize {
+        self.buffer().bytes().len().div_ceil(BYTES_PER_ROW)
+    }
+
+    //moves by whole bytes, up and down move by a row
+    fn move_hex(&mut self, direction: Move) {
+        #[allow(clippy::enum_glob_use)]
+        use Move::*;
+        let offset = self.hex_cursor.offset;
+        let page = self
+            .editor_height()
+            .saturating_sub(1)
+            .saturating_mul(BYTES_PER_ROW);
+        let row_start = self.hex_cursor.row().saturating_mul(BYTES_PER_ROW);
+        let offset = match direction {
+            Up => offset.checked_sub(BYTES_PER_ROW).unwrap_or(offset),
+            Down => offset.saturating_add(BYTES_PER_ROW),
+            PageUp => offset.saturating_sub(page),
+            PageDown => offset.saturating_add(page),
+            Left | LeftUp | WordLeft => offset.saturating_sub(1),
+            Right | RightUp | WordRight => offset.saturating_add(1),
+            StartOfLine => row_start,
+            EndOfLine => row_start.saturating_add(BYTES_PER_ROW.saturating_sub(1)),
+        };
+        let last = self.buffer().bytes().len().saturating_sub(1);
+        self.hex_cursor = HexCursor {
+            offset: std::cmp::min(offset, last),
+            low_nibble: false,
+        };
+    }
+
+    //typing hex digits overwrites the byte under the cursor nibble by nibble
+    fn hex_edit(&mut self, command: Edit) {
+        let Edit::Insert(char) = command else {
+            return;
+        };
+        let Some(nibble) = char.to_digit(16).and_then(|digit| u8::try_from(digit).ok()) else {
+            return;
+        };
+        let HexCursor { offset, low_nibble } = self.hex_cursor;
+        let Some(byte) = self.buffer().bytes().get(offset).copied() else {
+            return;
+        };
+        self.buffer_mut()
+            .set_byte(offset, hex::set_nibble(byte, nibble, low_nibble));
+        if low_nibble {
+            self.move_hex(Move::Right);
+        } else {
+            self.hex_cursor.low_nibble = true;
+        }
+        self.update_hex_scroll_offset();
+    }
+
+    fn update_hex_scroll_offset(&mut self) {
+        let row = self.hex_cursor.row();
+        let mut scroll_y = self.scroll_offset.row;
+        if row >= scroll_y.saturating_add(self.editor_height()) {
+            scroll_y = row.saturating_add(1).saturating_sub(self.editor_height());
+        } else if row < scroll_y {
+            scroll_y = row;
+        }
+        self.scroll_offset = Position {
+            col: 0,
+            row: scroll_y,
+        };
+        self.mark_redraw(true);
+    }
+
+    fn draw_hex(&self, origin: Position) -> Result<(), Error> {
+        let Size { width, height } = self.size;
+        let buffer = self.buffer();
+        let bytes = buffer.bytes();
+        let start_y = origin.row;
+        for current_row in start_y..start_y.saturating_add(height) {
+            let at = Position {
+                row: current_row,
+                col: origin.col,
+            };
+            let row = current_row
+                .saturating_sub(start_y)
+                .saturating_add(self.scroll_offset.row);
+            if row < self.hex_row_count() {
+                let start = row.saturating_mul(BYTES_PER_ROW);
+                let end = std::cmp::min(start.saturating_add(BYTES_PER_ROW), bytes.len());
+                let mut line = hex::render_row(&bytes[start..end], row);
+                line.truncate(width);
+                self.render_line(at, &line)?;
+            } else {
+                self.render_line(at, "~")?;
+            }
+        }
+        Ok(())
+    }
+    //end region: hex view
+
+    //region: soft wrap
+    fn soft_wrap(&self) -> bool {
+        let buffer = self.buffer();
+        buffer.settings.soft_wrap && !buffer.is_binary()
+    }
+
+    //the line past the end of the buffer is an empty one
+    fn wrapped_line(&self, y: usize) -> (Line, Vec<WrappedRow>) {
+        let line = self.buffer().line(y).unwrap_or_default();
+        let rows = line.wrap(self.text_width());
+        (line, rows)
+    }
+
+    //the row the cursor is in, at the start of the next row if it is between two
+    fn cursor_row(rows: &[WrappedRow], x: usize) -> usize {
+        rows.partition_point(|row| row.graphemes.start <= x)
+            .saturating_sub(1)
+    }
+
+    //visual rows from row `from.1` of line `from.0` to row `to.1` of line `to.0`,
+    //counting stops at `limit`
+    fn rows_between(&self, from: (usize, usize), to: (usize, usize), limit: usize) -> usize {
+        let (mut y, mut row) = from;
+        let mut count: usize = 0;
+        while y < to.0 && count < limit {
+            let row_count = self.wrapped_line(y).1.len();
+            count = count.saturating_add(row_count.saturating_sub(row));
+            y = y.saturating_add(1);
+            row = 0;
+        }
+        count.saturating_add(to.1.saturating_sub(row))
+    }
+
+    //the row of the cursor becomes the top row if it left the view
+    fn update_wrapped_scroll_offset(&mut self) {
+        let Location { x, y } = self.location;
+        let cursor = (y, Self::cursor_row(&self.wrapped_line(y).1, x));
+        let top = (self.scroll_offset.row, self.wrap_offset);
+        let height = self.editor_height();
+        if cursor < top || self.rows_between(top, cursor, height) >= height {
+            self.scroll_offset.row = cursor.0;
+            self.wrap_offset = cursor.1;
+        }
+        self.scroll_offset.col = 0;
+        self.mark_redraw(true);
+    }
+
+    //relative to the top left corner of the text
+    fn wrapped_caret_location(&self) -> Position {
+        let Location { x, y } = self.location;
+        let (line, rows) = self.wrapped_line(y);
+        let row = Self::cursor_row(&rows, x);
+        let row_start = rows.get(row).map_or(0, |row| row.columns.start);
+        let top = (self.scroll_offset.row, self.wrap_offset);
+        Position {
+            col: line.width_until(x).saturating_sub(row_start),
+            row: self.rows_between(top, (y, row), self.editor_height()),
+        }
+    }
+
+    //one visual row up or down at about the same column, false at either end of the buffer
+    fn move_row(&mut self, down: bool) -> bool {
+        let Location { x, y } = self.location;
+        let (line, rows) = self.wrapped_line(y);
+        let row = Self::cursor_row(&rows, x);
+        let row_start = rows.get(row).map_or(0, |row| row.columns.start);
+        let col = line.width_until(x).saturating_sub(row_start);
+        let (y, row) = if down && row.saturating_add(1) < rows.len() {
+            (y, row.saturating_add(1))
+        } else if down && y < self.buffer().line_count() {
+            (y.saturating_add(1), 0)
+        } else if !down && row > 0 {
+            (y, row.saturating_sub(1))
+        } else if !down && y > 0 {
+            let y = y.saturating_sub(1);
+            (y, self.wrapped_line(y).1.len().saturating_sub(1))
+        } else {
+            return false;
+        };
+        let (line, rows) = self.wrapped_line(y);
+        let Some(target) = rows.get(row) else {
+            return false;
+        };
+        let mut x = line.grapheme_at(target, col);
+        //the end of a row that isn't the last is the start of the next one
+        if row.saturating_add(1) < rows.len() {
+            x = x.min(target.graphemes.end.saturating_sub(1));
+        }
+        self.location = Location { x, y };
+        true
+    }
+
+    fn move_rows_up(&mut self, rows: usize) {
+        if !self.soft_wrap() {
+            self.move_up(rows);
+            return;
+        }
+        for _ in 0..rows {
+            if !self.move_row(false) {
+                break;
+            }
+        }
+    }
+
+    fn move_rows_down(&mut self, rows: usize) {
+        if !self.soft_wrap() {
+            self.move_down(rows);
+            return;
+        }
+        for _ in 0..rows {
+            if !self.move_row(true) {
+                break;
+            }
+        }
+    }
+
+    //rows of a line get the line number on the first one only
+    fn draw_wrapped(&self, origin: Position) -> Result<(), Error> {
+        let Size { height, .. } = self.size;
+        let width = self.text_width();
+        let gutter_width = self.gutter_width();
+        let end_y = origin.row.saturating_add(height);
+        self.buffer_mut()
+            .update_highlighting(self.scroll_offset.row.saturating_add(height));
+
+        let mut current_row = origin.row;
+        let mut y = self.scroll_offset.row;
+        let mut skip = self.wrap_offset;
+        while current_row < end_y {
+            let gutter_at = Position {
+                row: current_row,
+                col: origin.col,
+            };
+            let at = Position {
+                row: current_row,
+                col: origin.col.saturating_add(gutter_width),
+            };
+            let Some(line) = self.buffer().highlighted_line(y) else {
+                self.render_gutter(gutter_at, None)?;
+                self.render_line(at, "~")?;
+                current_row = current_row.saturating_add(1);
+                continue;
+            };
+            let rows = line.wrap(width);
+            let last = rows.len().saturating_sub(1);
+            for (index, row) in rows.iter().enumerate().skip(skip.min(last)) {
+                if current_row >= end_y {
+                    break;
+                }
+                let at = Position {
+                    row: current_row,
+                    ..at
+                };
+                let gutter_at = Position {
+                    row: current_row,
+                    ..gutter_at
+                };
+                self.render_gutter(gutter_at, (index == 0).then_some(y))?;
+                let left = row.columns.start;
+                //nothing comes after the last row, a selected line break shows there
+                let right = if index == last {
+                    left.saturating_add(width)
+                } else {
+                    row.columns.end
+                };
+                let columns = left..right;
+                self.render_spans(at, &line.get_spans(columns.clone()))?;
+                self.highlight_selection(at, y, &line, &columns)?;
+                self.highlight_matches(at, &line, &columns)?;
+                current_row = current_row.saturating_add(1);
+            }
+            skip = 0;
+            y = y.saturating_add(1);
+        }
+        Ok(())
+    }
+    //end region: soft wrap
+
     fn text_location_to_position(&self) -> Position {
         let Location { x, y } = self.location;
-        let x = self
-            .buffer
-            .lines
-            .get(y)
-            .map_or(0, |line| line.width_until(x));
+        let x = self.buffer().line(y).map_or(0, |line| line.width_until(x));
         Position { col: x, row: y }
     }
 
     fn update_scroll_offset(&mut self) {
+        if self.soft_wrap() {
+            self.update_wrapped_scroll_offset();
+            return;
+        }
         let editor_height = self.editor_height();
-        let Size { width, .. } = self.size;
+        let width = self.text_width();
         let Position { col: x, row: y } = self.text_location_to_position();
 
         let Position {
@@ -241,21 +1261,55 @@ impl View {
     }
 
     pub fn get_caret_location(&self) -> Position {
-        self.text_location_to_position()
-            .subtract(&self.scroll_offset)
+        if self.buffer().is_binary() {
+            return Position {
+                col: self.hex_cursor.column(),
+                row: self.hex_cursor.row(),
+            }
+            .subtract(&self.scroll_offset);
+        }
+        let Position { col, row } = if self.soft_wrap() {
+            self.wrapped_caret_location()
+        } else {
+            self.text_location_to_position()
+                .subtract(&self.scroll_offset)
+        };
+        Position {
+            col: col.saturating_add(self.gutter_width()),
+            row,
+        }
     }
 
     pub fn get_status(&self) -> DocumentStatus {
+        let buffer = self.buffer();
         DocumentStatus {
-            is_modified: self.buffer.is_modified,
+            is_modified: buffer.is_modified(),
             curr_location: self.location,
+            display_column: buffer
+                .line(self.location.y)
+                .map_or(0, |line| line.width_until(self.location.x)),
             //why clone in every rerender
-            filename: self.buffer.filename.clone(),
+            filename: buffer.filename.clone(),
+            line_ending: buffer.line_ending,
+            encoding: buffer.encoding,
+            mixed_line_endings: buffer.mixed_line_endings,
+            total_lines: buffer.line_count(),
+            is_indexing: buffer.is_indexing(),
+            load_progress: buffer.load_progress(),
+            byte_offset: buffer.is_binary().then_some(self.hex_cursor.offset),
         }
     }
 
+    pub fn line_ending(&self) -> LineEnding {
+        self.buffer().line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.buffer_mut().set_line_ending(line_ending);
+    }
+
     pub fn is_modified(&self) -> bool {
-        self.buffer.is_modified
+        self.buffer().is_modified()
     }
 
     /// UP
@@ -285,17 +1339,25 @@ impl View {
     pub fn handle_move_command(&mut self, direction: Move) {
         #[allow(clippy::enum_glob_use)]
         use Move::*;
+        if self.buffer().is_binary() {
+            self.move_hex(direction);
+            self.update_hex_scroll_offset();
+            return;
+        }
+        self.selection_anchor = None;
         match direction {
             //vertical
-            Up => self.move_up(1),
-            Down => self.move_down(1),
-            PageUp => self.move_up(self.editor_height().saturating_sub(1)),
-            PageDown => self.move_down(self.editor_height().saturating_sub(1)),
+            Up => self.move_rows_up(1),
+            Down => self.move_rows_down(1),
+            PageUp => self.move_rows_up(self.editor_height().saturating_sub(1)),
+            PageDown => self.move_rows_down(self.editor_height().saturating_sub(1)),
             //horizontal
             Left => self.move_left(),
             LeftUp => self.move_left_y(),
             RightUp => self.move_right_y(),
             Right => self.move_right(),
+            WordLeft => self.move_word_left(),
+            WordRight => self.move_word_right(),
             StartOfLine => self.move_to_start_of_line(),
             EndOfLine => self.move_to_end_of_line(),
         }
@@ -305,16 +1367,31 @@ impl View {
     pub fn handle_edit_command(&mut self, command: Edit) {
         #[allow(clippy::enum_glob_use)]
         use Edit::*;
+        if self.buffer().is_binary() {
+            self.hex_edit(command);
+            return;
+        }
+        let before = self.location;
+        //typing replaces the selection, deleting just removes it
+        let deleted = !matches!(command, Undo | Redo) && self.delete_selection();
         match command {
             Insert(char) => self.insert_char(char),
+            Indent => self.indent(),
             InsertNewLine => self.insert_new_line(),
+            Delete | DeleteBackward | DeleteWord | DeleteWordBackward if deleted => {}
             Delete => self.delete(),
+            DeleteWord => self.delete_word(),
+            DeleteWordBackward => self.delete_word_backward(),
+            DeleteBackward if self.unindent() => {}
             DeleteBackward => self.delete_backward(),
+            Undo => self.undo(),
+            Redo => self.redo(),
         }
+        self.buffer_mut().commit_edit(before, self.location);
     }
 
     pub fn is_unnamed(&self) -> bool {
-        self.buffer.filename.is_none()
+        self.buffer().filename.is_none()
     }
 }
 
@@ -329,11 +1406,24 @@ impl UIComponent for View {
 
     fn set_size(&mut self, size: Size) {
         self.size = size;
-        self.update_scroll_offset();
+        if self.buffer().is_binary() {
+            self.update_hex_scroll_offset();
+        } else {
+            self.update_scroll_offset();
+        }
     }
 
-    fn draw(&self, start_y: usize) -> Result<(), Error> {
-        let Size { width, height } = self.size;
+    fn draw(&self, origin: Position) -> Result<(), Error> {
+        if self.buffer().is_binary() {
+            return self.draw_hex(origin);
+        }
+        if self.soft_wrap() {
+            return self.draw_wrapped(origin);
+        }
+        let Size { height, .. } = self.size;
+        let width = self.text_width();
+        let gutter_width = self.gutter_width();
+        let start_y = origin.row;
         let end_y = start_y.saturating_add(height);
 
         let Position {
@@ -344,21 +1434,40 @@ impl UIComponent for View {
         #[allow(clippy::integer_division)]
         let vertical_center = start_y.saturating_add(height / 3);
 
+        self.buffer_mut()
+            .update_highlighting(scroll_y.saturating_add(height));
+
         for current_row in start_y..end_y {
+            let gutter_at = Position {
+                row: current_row,
+                col: origin.col,
+            };
+            let at = Position {
+                row: current_row,
+                col: origin.col.saturating_add(gutter_width),
+            };
             #[allow(clippy::integer_division)]
             let line_index = current_row.saturating_sub(start_y).saturating_add(scroll_y);
-            if let Some(line) = self.buffer.lines.get(line_index) {
+            let line = self.buffer().highlighted_line(line_index);
+            self.render_gutter(gutter_at, line.is_some().then_some(line_index))?;
+            if let Some(line) = line {
                 //not utf compliant?
-                let left = scroll_x;
-                let right = scroll_x.saturating_add(width);
-                Self::render_line(current_row, &line.get_graphemes(left..right))?;
-            } else if current_row == vertical_center && self.buffer.is_empty() {
-                Self::render_line(current_row, &Self::build_welcome_message(width))?;
+                let columns = scroll_x..scroll_x.saturating_add(width);
+                self.render_spans(at, &line.get_spans(columns.clone()))?;
+                self.highlight_selection(at, line_index, &line, &columns)?;
+                self.highlight_matches(at, &line, &columns)?;
+            } else if current_row == vertical_center && self.buffer().is_empty() {
+                self.render_line(at, &Self::build_welcome_message(width))?;
             } else {
-                Self::render_line(current_row, "~")?;
+                self.render_line(at, "~")?;
             }
         }
 
         Ok(())
     }
 }
+
+//whitespace that makes up indentation
+fn is_blank(grapheme: &str) -> bool {
+    grapheme == " " || grapheme == "\t"
+}
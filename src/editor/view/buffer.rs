@@ -1,86 +1,810 @@
+use super::encoding::{self, Encoding, Utf8Stats};
+use super::highlight::Highlighter;
+use super::indent::DETECT_LINES;
 use super::line::Line;
+use super::lineending::LineEnding;
 use super::Location;
+use crate::editor::config::{Config, FileSettings};
+use crate::editor::filetype::FileType;
 
-use std::fs::OpenOptions;
-use std::io::Error;
-use std::io::Write;
+use ropey::{Rope, RopeBuilder};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
 
+mod atomicwrite;
+mod diff;
+mod diskstate;
+mod history;
+mod stream;
+mod swap;
+
+use atomicwrite::{sync_parent, write_atomic};
+use diskstate::DiskState;
+use history::{Change, History};
+use stream::{FullLoad, LoadProgress, StreamedFile};
+use swap::SwapFile;
+
+//a file read and decoded, ready to be put into a buffer.
+//built off the ui thread when a streamed file is loaded fully
+#[derive(Default)]
+struct Contents {
+    text: Rope,
+    //binary files are kept as they are
+    binary: Option<Vec<u8>>,
+    encoding: Encoding,
+    lf_count: usize,
+    crlf_count: usize,
+    missing_final_newline: bool,
+}
+
+//collects decoded lines into a rope, counting their line endings
+#[derive(Default)]
+struct TextLoader {
+    builder: RopeBuilder,
+    lf_count: usize,
+    crlf_count: usize,
+    missing_final_newline: bool,
+}
+
+impl TextLoader {
+    //lines are kept with their ending, a missing one is added
+    fn push_line(&mut self, line: &str) {
+        self.missing_final_newline = !line.ends_with('\n');
+        if line.ends_with("\r\n") {
+            self.crlf_count = self.crlf_count.saturating_add(1);
+        } else if line.ends_with('\n') {
+            self.lf_count = self.lf_count.saturating_add(1);
+        }
+        self.builder.append(line);
+        if self.missing_final_newline {
+            self.builder.append("\n");
+        }
+    }
+
+    fn finish(self, encoding: Encoding) -> Contents {
+        Contents {
+            text: self.builder.finish(),
+            binary: None,
+            encoding,
+            lf_count: self.lf_count,
+            crlf_count: self.crlf_count,
+            missing_final_newline: self.missing_final_newline,
+        }
+    }
+}
+
+//every line in `text` is terminated by '\n', so "a\nb\n" holds two lines
+//and an empty rope holds none.
+//CRLF lines keep their '\r', it is never part of a `Line`. files with mixed
+//line endings are written back as they are, all others with `line_ending`.
+//the missing newline at the end of the file is only applied when writing it back.
+//`Line`s are only built on demand for the lines that are actually needed.
 #[derive(Default)]
 pub struct Buffer {
-    pub lines: Vec<Line>,
+    text: Rope,
     pub filename: Option<String>,
-    pub is_modified: bool,
+    history: History,
+    pub line_ending: LineEnding,
+    //`line_ending` and `mixed_line_endings` as of the last load or save
+    saved_line_endings: (LineEnding, bool),
+    //file has both LF and CRLF lines, kept until converted with `set_line_ending`
+    pub mixed_line_endings: bool,
+    missing_final_newline: bool,
+    pub encoding: Encoding,
+    pub file_type: FileType,
+    pub settings: FileSettings,
+    //binary files are kept as raw bytes and shown in the hex view instead of `text`
+    binary: Option<Vec<u8>>,
+    binary_modified: bool,
+    //large files are read from disk on demand until the first edit
+    stream: Option<StreamedFile>,
+    //loads a streamed file so it can be edited, the stream is used until it is done
+    full_load: Option<FullLoad<(Contents, Option<DiskState>)>>,
+    //the file as of the last load or save, to notice changes by other programs
+    disk_state: Option<DiskState>,
+    //a change on disk the user chose to keep editing over
+    ignored_disk_state: Option<DiskState>,
+    //unsaved edits are written here periodically so they survive a crash
+    swap: Option<SwapFile>,
+    //counts changes of the contents, views sharing the buffer compare it to notice them
+    revision: usize,
+    //cursor of the last view that stopped showing the buffer
+    pub last_location: Location,
+    //tokens of the lines drawn so far, kept up to date through `apply`
+    highlighter: Highlighter,
+    //line and char offsets of the grapheme boundaries of the line looked up last,
+    //so typing doesn't go over the whole line for every lookup. dropped by `apply`
+    grapheme_offsets: RefCell<Option<(usize, Rc<[usize]>)>>,
 }
 impl Buffer {
-    pub fn load(filename: &str) -> Result<Self, Error> {
+    pub fn load(filename: &str, config: &Config) -> Result<Self, Error> {
+        let mut buffer = Buffer {
+            filename: Some(String::from(filename)),
+            ..Self::default()
+        };
+        buffer.update_file_type(config);
         if std::path::Path::new(filename).exists() {
-            let data = std::fs::read_to_string(filename)?;
-            let mut buffer = Buffer {
-                filename: Some(String::from(filename)),
+            let is_large = std::fs::metadata(filename)?.len() >= buffer.settings.large_file_size;
+            if !(is_large && buffer.open_streamed(filename)?) {
+                let contents = Self::read_contents(Path::new(filename), &LoadProgress::default())?;
+                buffer.set_contents(contents);
+            }
+            buffer.record_disk_state();
+        }
+        buffer.detect_indentation();
+        Ok(buffer)
+    }
+
+    //the swap file and its lock stay with the document
+    pub fn reload(&mut self, config: &Config) -> Result<(), Error> {
+        let Some(filename) = self.filename.as_ref() else {
+            return Ok(());
+        };
+        let mut buffer = Self::load(filename, config)?;
+        buffer.swap = self.swap.take();
+        buffer.revision = self.revision.wrapping_add(1);
+        buffer.last_location = self.last_location;
+        *self = buffer;
+        Ok(())
+    }
+
+    fn record_disk_state(&mut self) {
+        self.disk_state = self
+            .filename
+            .as_ref()
+            .and_then(|filename| DiskState::read(Path::new(filename), !self.is_streaming()).ok());
+        self.ignored_disk_state = None;
+    }
+
+    //true if another program changed the file since it was loaded or saved
+    pub fn changed_on_disk(&mut self) -> bool {
+        let (Some(filename), Some(disk_state)) = (self.filename.as_ref(), self.disk_state.as_mut())
+        else {
+            return false;
+        };
+        !disk_state.is_current(Path::new(filename))
+    }
+
+    //like `changed_on_disk` but without the change the user already dismissed
+    pub fn has_new_disk_change(&mut self) -> bool {
+        if !self.changed_on_disk() {
+            return false;
+        }
+        let (Some(filename), Some(ignored)) =
+            (self.filename.as_ref(), self.ignored_disk_state.as_mut())
+        else {
+            return true;
+        };
+        !ignored.is_current(Path::new(filename))
+    }
+
+    pub fn ignore_disk_change(&mut self) {
+        self.ignored_disk_state = self
+            .filename
+            .as_ref()
+            .and_then(|filename| DiskState::read(Path::new(filename), !self.is_streaming()).ok());
+    }
+
+    //what overwriting the file would change, None if nothing
+    pub fn diff_with_disk(&self, config: &Config) -> Result<Option<String>, Error> {
+        let Some(filename) = self.filename.as_ref() else {
+            return Ok(None);
+        };
+        let on_disk = Self::load(filename, config)?;
+        Ok(diff::unified(
+            &on_disk,
+            &format!("{filename} on disk"),
+            self,
+            &format!("{filename} in the editor"),
+        ))
+    }
+
+    //true if a swap file of an earlier session is waiting to be recovered
+    pub fn open_swap(&mut self) -> Result<bool, Error> {
+        self.swap = None;
+        let Some(filename) = self.filename.as_ref() else {
+            return Ok(false);
+        };
+        let swap = SwapFile::open(filename)?;
+        let is_stale = swap.is_stale();
+        self.swap = Some(swap);
+        Ok(is_stale)
+    }
+
+    pub fn has_stale_swap(&self) -> bool {
+        self.swap.as_ref().is_some_and(SwapFile::is_stale)
+    }
+
+    //nothing is written without new edits, an unmodified buffer needs no swap file
+    pub fn write_swap(&mut self) -> Result<(), Error> {
+        let is_modified = self.is_modified();
+        let Some(swap) = self.swap.as_mut().filter(|swap| !swap.is_stale()) else {
+            return Ok(());
+        };
+        if !is_modified {
+            return swap.discard();
+        }
+        if swap.is_written(self.revision) {
+            return Ok(());
+        }
+        let text = &self.text;
+        let binary = self.binary.as_deref();
+        swap.write(self.revision, |writer| match binary {
+            Some(bytes) => writer.write_all(bytes),
+            None => text.write_to(writer),
+        })?;
+        Ok(())
+    }
+
+    //replaces the contents with the swap file as one undoable edit
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        if self.is_streaming() {
+            return Err(Error::other("the file has to be loaded fully first"));
+        }
+        let Some(contents) = self.swap.as_ref().map(SwapFile::read).transpose()? else {
+            return Ok(());
+        };
+        if let Some(bytes) = self.binary.as_mut() {
+            *bytes = contents;
+            self.binary_modified = true;
+        } else {
+            let text = String::from_utf8_lossy(&contents);
+            self.remove(0..self.text.len_chars());
+            self.insert(0, &text);
+            self.history
+                .commit(Location::default(), Location::default());
+        }
+        if let Some(swap) = self.swap.as_mut() {
+            swap.take_over();
+        }
+        self.revision = self.revision.wrapping_add(1);
+        Ok(())
+    }
+
+    //what recovering the swap file would change, None if nothing
+    pub fn diff_with_swap(&self) -> Result<Option<String>, Error> {
+        let Some(contents) = self.swap.as_ref().map(SwapFile::read).transpose()? else {
+            return Ok(None);
+        };
+        let swapped = if self.is_binary() {
+            Buffer {
+                binary: Some(contents),
                 ..Self::default()
-            };
-            for line in data.lines() {
-                buffer.lines.push(Line::from(line));
             }
-            Ok(buffer)
         } else {
-            let buffer = Buffer {
-                filename: Some(String::from(filename)),
+            Buffer {
+                text: Rope::from_str(&String::from_utf8_lossy(&contents)),
                 ..Self::default()
-            };
-            Ok(buffer)
+            }
+        };
+        let name = self.filename.as_deref().unwrap_or_default();
+        Ok(diff::unified(
+            self,
+            &format!("{name} in the editor"),
+            &swapped,
+            &format!("{name} in the swap file"),
+        ))
+    }
+
+    //an unnamed buffer holding `text`, like a diff to look at
+    pub fn scratch(text: &str, config: &Config) -> Self {
+        let mut buffer = Buffer {
+            text: Rope::from_str(text),
+            ..Self::default()
+        };
+        buffer.update_file_type(config);
+        buffer
+    }
+
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        self.swap.as_mut().map_or(Ok(()), SwapFile::discard)
+    }
+
+    //falls back to windows-1252 if the file doesn't look like utf-8
+    fn read_contents(path: &Path, progress: &LoadProgress) -> Result<Contents, Error> {
+        let (contents, stats) = Self::read_file(path, None, progress)?;
+        if contents.encoding == Encoding::Utf8 && stats.looks_like_windows_1252() {
+            return Ok(Self::read_file(path, Some(Encoding::Windows1252), progress)?.0);
+        }
+        Ok(contents)
+    }
+
+    //only plain utf-8 text is streamed, false if the file has to be loaded normally.
+    //like for files loaded normally, binary content and the encoding are only
+    //sniffed from the start of the file. the full load before editing decodes
+    //all of it and falls back to windows-1252 if needed
+    fn open_streamed(&mut self, filename: &str) -> Result<bool, Error> {
+        let mut reader = BufReader::new(File::open(filename)?);
+        let head = reader.fill_buf()?;
+        if Encoding::detect(head).is_some() || encoding::looks_binary(head) {
+            return Ok(false);
+        }
+        let crlf_count = head.windows(2).filter(|pair| pair == b"\r\n").count();
+        #[allow(clippy::naive_bytecount)]
+        let lf_count = head
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count()
+            .saturating_sub(crlf_count);
+        self.line_ending = LineEnding::detect(lf_count, crlf_count);
+        self.saved_line_endings = self.line_endings();
+        self.stream = Some(StreamedFile::open(Path::new(filename))?);
+        Ok(true)
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.stream.as_ref().is_some_and(StreamedFile::is_indexing)
+    }
+
+    //streamed files are loaded completely before they can be edited,
+    //in the background as that can take a while. nothing happens if already started
+    pub fn start_full_load(&mut self) {
+        let (Some(stream), Some(filename), None) = (
+            self.stream.as_ref(),
+            self.filename.as_ref(),
+            self.full_load.as_ref(),
+        ) else {
+            return;
+        };
+        let path = Path::new(filename).to_path_buf();
+        self.full_load = Some(FullLoad::start(stream.len(), move |progress| {
+            let before = DiskState::read(&path, false)?;
+            let contents = Self::read_contents(&path, progress)?;
+            //the hash is only right if the file didn't change while it was read
+            let disk_state = DiskState::read(&path, true)
+                .ok()
+                .filter(|disk_state| disk_state.same_file(&before));
+            Ok((contents, disk_state))
+        }));
+    }
+
+    //percent of the file read so far by `start_full_load`
+    pub fn load_progress(&self) -> Option<u64> {
+        self.full_load.as_ref().map(FullLoad::percent)
+    }
+
+    //Some once the load started by `start_full_load` is done
+    pub fn finish_full_load(&mut self) -> Option<Result<(), Error>> {
+        let (contents, disk_state) = match self.full_load.as_ref()?.poll()? {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.full_load = None;
+                return Some(Err(err));
+            }
+        };
+        self.full_load = None;
+        self.stream = None;
+        self.set_contents(contents);
+        //keeps the state from opening the file if it changed since, so the change is noticed
+        if let (Some(old), Some(new)) = (self.disk_state.as_ref(), disk_state) {
+            if old.same_file(&new) {
+                self.disk_state = Some(new);
+            }
+        }
+        self.detect_indentation();
+        self.revision = self.revision.wrapping_add(1);
+        Some(Ok(()))
+    }
+
+    //decodes the file line by line, utf-16 has to be decoded as a whole
+    //as its newline is more than a single byte
+    fn read_file(
+        path: &Path,
+        encoding: Option<Encoding>,
+        progress: &LoadProgress,
+    ) -> Result<(Contents, Utf8Stats), Error> {
+        let mut reader = BufReader::new(progress.reader(File::open(path)?));
+        let encoding = if let Some(encoding) = encoding {
+            encoding
+        } else {
+            let head = reader.fill_buf()?;
+            match Encoding::detect(head) {
+                Some(encoding) => encoding,
+                None if encoding::looks_binary(head) => {
+                    let mut bytes = Vec::new();
+                    reader.read_to_end(&mut bytes)?;
+                    let contents = Contents {
+                        binary: Some(bytes),
+                        ..Contents::default()
+                    };
+                    return Ok((contents, Utf8Stats::default()));
+                }
+                None => Encoding::default(),
+            }
+        };
+        reader.consume(encoding.bom().len());
+
+        let mut stats = Utf8Stats::default();
+        let mut loader = TextLoader::default();
+        let mut bytes = Vec::new();
+        if encoding.is_utf16() {
+            reader.read_to_end(&mut bytes)?;
+            for line in encoding.decode(&bytes, &mut stats).split_inclusive('\n') {
+                loader.push_line(line);
+            }
+        } else {
+            while reader.read_until(b'\n', &mut bytes)? > 0 {
+                loader.push_line(&encoding.decode(&bytes, &mut stats));
+                bytes.clear();
+            }
+        }
+        Ok((loader.finish(encoding), stats))
+    }
+
+    fn set_contents(&mut self, contents: Contents) {
+        self.text = contents.text;
+        self.binary = contents.binary;
+        *self.grapheme_offsets.get_mut() = None;
+        self.encoding = contents.encoding;
+        self.line_ending = LineEnding::detect(contents.lf_count, contents.crlf_count);
+        self.mixed_line_endings = contents.lf_count > 0 && contents.crlf_count > 0;
+        self.saved_line_endings = self.line_endings();
+        self.missing_final_newline = contents.missing_final_newline;
+        self.highlighter.clear();
+    }
+
+    fn update_file_type(&mut self, config: &Config) {
+        self.file_type = self
+            .filename
+            .as_deref()
+            .map_or(FileType::default(), FileType::from_filename);
+        self.settings = config.file_settings(self.file_type);
+        self.highlighter = Highlighter::new(self.file_type);
+    }
+
+    fn detect_indentation(&mut self) {
+        if !self.settings.detect_indent || self.is_binary() {
+            return;
         }
+        let lines: Vec<String> = (0..self.line_count())
+            .map_while(|y| self.line_str(y))
+            .take(DETECT_LINES)
+            .collect();
+        self.settings.indentation = self
+            .settings
+            .indentation
+            .detect(lines.iter().map(String::as_str));
     }
 
     pub fn set_file_name(&mut self, filename: String) {
         self.filename = Some(filename);
     }
-    pub fn save_as(&mut self, filename: String) -> Result<(), Error> {
+    pub fn save_as(&mut self, filename: String, config: &Config) -> Result<(), Error> {
         self.set_file_name(filename);
-        self.save_file()?;
-        Ok(())
+        self.update_file_type(config);
+        self.detect_indentation();
+        let result = self.save_file();
+        //saved unless writing it failed, it just goes without swap file on errors
+        if !self.is_modified() {
+            let _ = self.open_swap();
+        }
+        result
     }
     pub fn save_file(&mut self) -> Result<(), Error> {
         //do nothing if filename doesnot exist
-        if self.filename.is_none() {
+        let Some(filename) = self.filename.as_ref() else {
             return Ok(());
+        };
+
+        let path = Path::new(filename).to_path_buf();
+        write_atomic(&path, |writer| self.write_to(writer))?;
+        self.record_disk_state();
+        self.history.mark_saved();
+        self.saved_line_endings = self.line_endings();
+        self.binary_modified = false;
+        if self.settings.insert_final_newline {
+            self.missing_final_newline = false;
         }
-        let mut fileptr = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(self.filename.as_ref().unwrap())
-            .expect("couldn't open file");
+        sync_parent(&path).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("saved, but the directory couldnot be synced: {err}"),
+            )
+        })?;
+        //only once the saved file is durable, a stale swap file is left for the user to decide
+        self.swap
+            .as_mut()
+            .filter(|swap| !swap.is_stale())
+            .map_or(Ok(()), SwapFile::discard)
+            .map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("saved, but the swap file couldnot be removed: {err}"),
+                )
+            })
+    }
 
-        //just build a string for now
-        let mut content = String::new();
-        for line in &self.lines {
-            content.push_str(&line.to_string());
-            content.push('\n'); // write \r\n to windows(maybe autodetect)
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        if let Some(bytes) = self.binary.as_ref() {
+            return writer.write_all(bytes);
+        }
+        if let Some(stream) = self.stream.as_ref() {
+            return stream.copy_to(writer);
+        }
+        let end = if self.missing_final_newline && !self.settings.insert_final_newline {
+            self.text.len_chars().saturating_sub(1)
+        } else {
+            self.text.len_chars()
+        };
+        writer.write_all(self.encoding.bom())?;
+        let mut bytes = Vec::new();
+        if self.mixed_line_endings {
+            for chunk in self.text.slice(..end).chunks() {
+                self.encoding.encode(chunk, &mut bytes)?;
+                writer.write_all(&bytes)?;
+                bytes.clear();
+            }
+            return Ok(());
+        }
+        //a '\r' at the end of a chunk may be the start of a "\r\n" going on in the next one
+        let mut text = String::new();
+        for chunk in self.text.slice(..end).chunks() {
+            text.push_str(chunk);
+            let carry = text.ends_with('\r');
+            if carry {
+                text.pop();
+            }
+            let normalized = text
+                .replace("\r\n", "\n")
+                .replace('\n', self.line_ending.as_str());
+            self.encoding.encode(&normalized, &mut bytes)?;
+            writer.write_all(&bytes)?;
+            bytes.clear();
+            text.clear();
+            if carry {
+                text.push('\r');
+            }
         }
+        self.encoding.encode(&text, &mut bytes)?;
+        writer.write_all(&bytes)
+    }
 
-        fileptr.write_all(content.as_bytes())?;
-        self.is_modified = false;
-        Ok(())
+    //also converts the lines of a file with mixed line endings
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+        self.mixed_line_endings = false;
+    }
+
+    fn line_endings(&self) -> (LineEnding, bool) {
+        (self.line_ending, self.mixed_line_endings)
+    }
+
+    pub fn is_modified(&self) -> bool {
+        !self.history.is_at_saved()
+            || self.line_endings() != self.saved_line_endings
+            || self.binary_modified
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.binary.is_some()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.binary.as_deref().unwrap_or_default()
+    }
+
+    //hex editing only overwrites, the file size never changes
+    pub fn set_byte(&mut self, offset: usize, byte: u8) {
+        if let Some(old) = self.binary.as_mut().and_then(|bytes| bytes.get_mut(offset)) {
+            if *old != byte {
+                *old = byte;
+                self.binary_modified = true;
+                self.mark_changed();
+            }
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.lines.is_empty()
+        self.line_count() == 0
+    }
+
+    pub fn line_count(&self) -> usize {
+        if let Some(stream) = self.stream.as_ref() {
+            return stream.line_count();
+        }
+        //ropey counts the empty line after the final '\n' too
+        self.text.len_lines().saturating_sub(1)
+    }
+
+    fn line_str(&self, y: usize) -> Option<String> {
+        if let Some(stream) = self.stream.as_ref() {
+            return stream.line(y);
+        }
+        if y >= self.line_count() {
+            return None;
+        }
+        let mut string = self.text.line(y).to_string();
+        string.pop(); // '\n'
+        if string.ends_with('\r') {
+            string.pop();
+        }
+        Some(string)
+    }
+
+    pub fn line(&self, y: usize) -> Option<Line> {
+        self.line_str(y)
+            .map(|string| Line::with_tab_width(&string, self.settings.tab_width))
+    }
+
+    //streamed files are too big to be lexed from the start, they stay plain
+    pub fn update_highlighting(&mut self, until: usize) {
+        if self.stream.is_none() {
+            self.highlighter.update(&self.text, until);
+        }
+    }
+
+    //annotated with its tokens if `update_highlighting` got to it
+    pub fn highlighted_line(&self, y: usize) -> Option<Line> {
+        let mut line = self.line(y)?;
+        if let Some(tokens) = self.highlighter.tokens(y) {
+            line.annotate(tokens);
+        }
+        Some(line)
+    }
+
+    //char offsets from the start of line `y` of its graphemes and of its end
+    fn grapheme_offsets(&self, y: usize) -> Rc<[usize]> {
+        if let Some((cached_y, offsets)) = self.grapheme_offsets.borrow().as_ref() {
+            if *cached_y == y {
+                return Rc::clone(offsets);
+            }
+        }
+        let mut offsets = vec![0];
+        if let Some(string) = self.line_str(y) {
+            let mut offset: usize = 0;
+            for grapheme in string.graphemes(true) {
+                offset = offset.saturating_add(grapheme.chars().count());
+                offsets.push(offset);
+            }
+        }
+        let offsets: Rc<[usize]> = offsets.into();
+        *self.grapheme_offsets.borrow_mut() = Some((y, Rc::clone(&offsets)));
+        offsets
+    }
+
+    pub fn grapheme_count(&self, y: usize) -> usize {
+        self.grapheme_offsets(y).len().saturating_sub(1)
+    }
+
+    //char index into `text` of the grapheme at `location`
+    //x beyond the end of the line maps to the end of the line
+    fn char_index(&self, location: Location) -> usize {
+        let Location { x, y } = location;
+        let line_start = self.text.line_to_char(y);
+        let offsets = self.grapheme_offsets(y);
+        let offset = offsets
+            .get(x)
+            .or(offsets.last())
+            .copied()
+            .unwrap_or_default();
+        line_start.saturating_add(offset)
+    }
+
+    //all mutations of `text` go through `insert` and `remove` so they can be undone
+    fn insert(&mut self, at: usize, text: &str) {
+        let change = Change::Insert {
+            at,
+            text: text.to_string(),
+        };
+        Self::apply(
+            &mut self.text,
+            &mut self.highlighter,
+            self.grapheme_offsets.get_mut(),
+            &change,
+        );
+        self.history.record(change);
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        let change = Change::Remove {
+            at: range.start,
+            text: self.text.slice(range).to_string(),
+        };
+        Self::apply(
+            &mut self.text,
+            &mut self.highlighter,
+            self.grapheme_offsets.get_mut(),
+            &change,
+        );
+        self.history.record(change);
+    }
+
+    fn apply(
+        text: &mut Rope,
+        highlighter: &mut Highlighter,
+        grapheme_offsets: &mut Option<(usize, Rc<[usize]>)>,
+        change: &Change,
+    ) {
+        *grapheme_offsets = None;
+        match change {
+            Change::Insert { at, text: inserted } => {
+                highlighter.edit(text.char_to_line(*at), 0, inserted.matches('\n').count());
+                text.insert(*at, inserted);
+            }
+            Change::Remove { at, text: removed } => {
+                highlighter.edit(text.char_to_line(*at), removed.matches('\n').count(), 0);
+                text.remove(*at..at.saturating_add(removed.chars().count()));
+            }
+        }
+    }
+
+    //groups the changes since the last commit into one undo step
+    pub fn commit_edit(&mut self, before: Location, after: Location) {
+        self.history.commit(before, after);
+        self.mark_changed();
+    }
+
+    //never merged with typing, for edits like pasting or replacing one match after another
+    pub fn commit_step(&mut self, before: Location, after: Location) {
+        self.history.commit_separate(before, after);
+        self.mark_changed();
+    }
+
+    fn mark_changed(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    //returns the cursor location from before the undone edit
+    pub fn undo(&mut self) -> Option<Location> {
+        let transaction = self.history.undo()?;
+        for change in transaction.changes.iter().rev() {
+            Self::apply(
+                &mut self.text,
+                &mut self.highlighter,
+                self.grapheme_offsets.get_mut(),
+                &change.inverse(),
+            );
+        }
+        let before = transaction.before;
+        self.mark_changed();
+        Some(before)
     }
 
+    //returns the cursor location from after the redone edit
+    pub fn redo(&mut self) -> Option<Location> {
+        let transaction = self.history.redo()?;
+        for change in &transaction.changes {
+            Self::apply(
+                &mut self.text,
+                &mut self.highlighter,
+                self.grapheme_offsets.get_mut(),
+                change,
+            );
+        }
+        let after = transaction.after;
+        self.mark_changed();
+        Some(after)
+    }
+
+    //typed characters, pasting into the terminal types them too.
+    //pasting the clipboard goes through `insert_text`, it holds text copied from a buffer
     pub fn insert_char(&mut self, char: char, text_location: Location) {
-        let Location { x, y } = text_location;
-        if let Some(line) = self.lines.get_mut(y) {
-            line.insert_char(char, x);
-            self.is_modified = true;
-        } else if y == self.lines.len() {
-            self.lines.push(Line::from(&char.to_string()));
-            self.is_modified = true;
+        let Location { y, .. } = text_location;
+        let text = self.encoding.escape(&char.to_string()).into_owned();
+        if y < self.line_count() {
+            let index = self.char_index(text_location);
+            self.insert(index, &text);
+        } else if y == self.line_count() {
+            let end = self.text.len_chars();
+            self.insert(end, &self.with_line_ending(&format!("{text}\n")));
         }
     }
 
     pub fn is_last_line(&self, y: usize) -> bool {
-        self.lines.len().saturating_sub(1) == y
+        self.line_count().saturating_sub(1) == y
     }
 
     /*
@@ -91,11 +815,12 @@ impl Buffer {
     pub fn delete(&mut self, location: Location) {
         let Location { x, y } = location;
 
-        let is_end_of_line = x == self.lines.get(y).map_or(0, Line::grapheme_count);
+        let grapheme_count = self.grapheme_count(y);
+        let is_end_of_line = x >= grapheme_count;
         let is_last_line = self.is_last_line(y);
 
         //beyond bottom right
-        if y >= self.lines.len() {
+        if y >= self.line_count() {
             return;
         }
         // bottom right
@@ -103,29 +828,96 @@ impl Buffer {
             return;
         }
         if is_end_of_line {
-            //remove and get works because it isn't last line and beyond if it is eol
-            let removed_line = self.lines.remove(y.saturating_add(1));
-            let line = self.lines.get_mut(y).unwrap();
-            line.concat(&removed_line);
-            self.is_modified = true;
-        } else if let Some(line) = self.lines.get_mut(y) {
-            line.remove_grapheme_at(x);
-            self.is_modified = true;
-        }
-    }
-
-    pub fn insert_new_line(&mut self, location: Location) {
-        if let Some(line) = self.lines.get_mut(location.y) {
-            let new_line = line.split_off(location.x);
-            self.lines.insert(location.y.saturating_add(1), new_line);
-            self.is_modified = true;
-        } else if location.y == self.lines.len() {
-            self.lines.push(Line::default());
-            self.is_modified = true;
+            //removing the line ending concats the next line, exists as it isn't the last line
+            let end = self.char_index(Location {
+                x: grapheme_count,
+                y,
+            });
+            let next_line = self.text.line_to_char(y.saturating_add(1));
+            self.remove(end..next_line);
+        } else {
+            let start = self.char_index(location);
+            let end = self.char_index(Location {
+                x: x.saturating_add(1),
+                y,
+            });
+            self.remove(start..end);
+        }
+    }
+
+    //text between two locations, lines are separated by '\n'
+    pub fn text_range(&self, start: Location, end: Location) -> String {
+        let start = self.char_index(start);
+        let end = self.char_index(end);
+        if start >= end {
+            return String::new();
+        }
+        self.text
+            .slice(start..end)
+            .to_string()
+            .replace("\r\n", "\n")
+    }
+
+    //new line breaks get `line_ending`
+    fn with_line_ending(&self, text: &str) -> String {
+        match self.line_ending {
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+            LineEnding::Lf => text.to_string(),
+        }
+    }
+
+    pub fn remove_range(&mut self, start: Location, end: Location) {
+        let start = self.char_index(start);
+        let end = self.char_index(end);
+        if start < end {
+            self.remove(start..end);
+        }
+    }
+
+    //`text` may span several lines, returns the location right after it
+    pub fn insert_text(&mut self, location: Location, text: &str) -> Location {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let Location { y, .. } = location;
+        if y < self.line_count() {
+            let index = self.char_index(location);
+            self.insert(index, &self.with_line_ending(&text));
+        } else if y == self.line_count() && !text.is_empty() {
+            //every line ends with '\n'
+            let end = self.text.len_chars();
+            if text.ends_with('\n') {
+                self.insert(end, &self.with_line_ending(&text));
+            } else {
+                self.insert(end, &self.with_line_ending(&format!("{text}\n")));
+            }
         } else {
-            self.lines
-                .insert(location.y.saturating_add(1), Line::default());
-            self.is_modified = true;
+            return location;
+        }
+        match text.rsplit_once('\n') {
+            Some((before, last)) => Location {
+                x: last.graphemes(true).count(),
+                y: y.saturating_add(before.matches('\n').count())
+                    .saturating_add(1),
+            },
+            None => Location {
+                x: location.x.saturating_add(text.graphemes(true).count()),
+                y,
+            },
+        }
+    }
+
+    //replaces the graphemes in `range` of line `y`, left uncommitted
+    pub fn replace(&mut self, y: usize, range: Range<usize>, text: &str) {
+        if y >= self.line_count() {
+            return;
+        }
+        let start = self.char_index(Location { x: range.start, y });
+        let end = self.char_index(Location { x: range.end, y });
+        if start < end {
+            self.remove(start..end);
+        }
+        if !text.is_empty() {
+            self.insert(start, &self.with_line_ending(text));
         }
+        self.mark_changed();
     }
 }
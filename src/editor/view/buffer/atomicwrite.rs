@@ -0,0 +1,113 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Error, IntoInnerError};
+use std::path::{Path, PathBuf};
+
+//symlinks are followed so the link itself survives the rename,
+//a dangling link resolves to the file it would point to
+fn resolve_target(path: &Path) -> PathBuf {
+    if let Ok(real_path) = fs::canonicalize(path) {
+        return real_path;
+    }
+    match fs::read_link(path) {
+        Ok(target) if target.is_relative() => path
+            .parent()
+            .map_or(target.clone(), |parent| parent.join(&target)),
+        Ok(target) => target,
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temp_name = format!(".{name}.mim-{}.tmp", std::process::id());
+    target.with_file_name(temp_name)
+}
+
+#[cfg(unix)]
+fn copy_ownership(original: &fs::Metadata, file: &File) {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    //only root may give the file away, keeping our own ownership is the best we can do then
+    let _ = fchown(file, Some(original.uid()), Some(original.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_original: &fs::Metadata, _file: &File) {}
+
+//created with the permissions of the original so its contents are never
+//readable by more users than the file they replace, not even for a moment
+#[cfg(unix)]
+fn create_temp(temp: &Path, original: Option<&fs::Metadata>) -> Result<File, Error> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    if let Some(original) = original {
+        options.mode(original.permissions().mode() & 0o777);
+    }
+    options.open(temp)
+}
+
+#[cfg(not(unix))]
+fn create_temp(temp: &Path, _original: Option<&fs::Metadata>) -> Result<File, Error> {
+    OpenOptions::new().write(true).create_new(true).open(temp)
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), Error> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+fn write_temp<F>(temp: &Path, original: Option<&fs::Metadata>, write: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+{
+    let file = create_temp(temp, original)?;
+    //before any contents are written, changing the owner may clear the setuid bits
+    if let Some(original) = original {
+        copy_ownership(original, &file);
+        file.set_permissions(original.permissions())?;
+    }
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(IntoInnerError::into_error)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+//writes to a temp file next to the target, syncs it and renames it over the target
+//so a failed save never leaves a half written file behind.
+//the rename is only durable once `sync_parent` succeeds too
+pub fn write_atomic<F>(path: &Path, write: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+{
+    let target = resolve_target(path);
+    let original = fs::metadata(&target).ok();
+    let temp = temp_path(&target);
+
+    if let Err(err) = write_temp(&temp, original.as_ref(), write) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    if let Err(err) = fs::rename(&temp, &target) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+//syncs the directory `write_atomic` renamed the file in. by then the new
+//contents are in place, so callers treat a failure as done but not durable
+pub fn sync_parent(path: &Path) -> Result<(), Error> {
+    let target = resolve_target(path);
+    match target.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => sync_dir(dir),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,133 @@
+use super::Buffer;
+
+//unchanged lines shown around the changed ones
+const CONTEXT: usize = 3;
+
+//lines before the first and after the last difference, the same in both.
+//None if there is no difference
+fn unchanged_ends(old: &Buffer, new: &Buffer) -> Option<(usize, usize)> {
+    let old_count = old.line_count();
+    let new_count = new.line_count();
+
+    let mut prefix = 0;
+    while prefix < old_count.min(new_count) && old.line_str(prefix) == new.line_str(prefix) {
+        prefix = prefix.saturating_add(1);
+    }
+    if prefix == old_count && prefix == new_count {
+        return None;
+    }
+
+    let mut suffix = 0;
+    while suffix < old_count.saturating_sub(prefix)
+        && suffix < new_count.saturating_sub(prefix)
+        && old.line_str(old_count.saturating_sub(suffix).saturating_sub(1))
+            == new.line_str(new_count.saturating_sub(suffix).saturating_sub(1))
+    {
+        suffix = suffix.saturating_add(1);
+    }
+    Some((prefix, suffix))
+}
+
+fn push_lines(diff: &mut String, buffer: &Buffer, lines: std::ops::Range<usize>, marker: char) {
+    for y in lines {
+        diff.push(marker);
+        diff.push_str(&buffer.line_str(y).unwrap_or_default());
+        diff.push('\n');
+    }
+}
+
+//binary files only get a count of the bytes that differ
+fn bytes(old: &[u8], old_name: &str, new: &[u8], new_name: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let changed = old.iter().zip(new).filter(|(old, new)| old != new).count();
+    Some(format!(
+        "{changed} bytes differ\n{} bytes in {old_name}\n{} bytes in {new_name}\n",
+        old.len(),
+        new.len()
+    ))
+}
+
+//unified diff with a single hunk going from the first to the last changed line,
+//everything in between shows up as removed and added. None if there is no difference
+pub fn unified(old: &Buffer, old_name: &str, new: &Buffer, new_name: &str) -> Option<String> {
+    if old.is_binary() || new.is_binary() {
+        return bytes(old.bytes(), old_name, new.bytes(), new_name);
+    }
+    let (prefix, suffix) = unchanged_ends(old, new)?;
+    let old_changed_end = old.line_count().saturating_sub(suffix);
+    let new_changed_end = new.line_count().saturating_sub(suffix);
+    let start = prefix.saturating_sub(CONTEXT);
+    let old_end = old_changed_end
+        .saturating_add(CONTEXT)
+        .min(old.line_count());
+    let new_end = new_changed_end
+        .saturating_add(CONTEXT)
+        .min(new.line_count());
+
+    let mut diff = format!(
+        "--- {old_name}\n+++ {new_name}\n@@ -{},{} +{},{} @@\n",
+        start.saturating_add(1),
+        old_end.saturating_sub(start),
+        start.saturating_add(1),
+        new_end.saturating_sub(start),
+    );
+    push_lines(&mut diff, new, start..prefix, ' ');
+    push_lines(&mut diff, old, prefix..old_changed_end, '-');
+    push_lines(&mut diff, new, prefix..new_changed_end, '+');
+    push_lines(&mut diff, new, new_changed_end..new_end, ' ');
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ropey::Rope;
+
+    fn buffer(text: &str) -> Buffer {
+        Buffer {
+            text: Rope::from_str(text),
+            ..Buffer::default()
+        }
+    }
+
+    #[test]
+    fn same_text_has_no_diff() {
+        assert_eq!(
+            unified(&buffer("a\nb\n"), "old", &buffer("a\nb\n"), "new"),
+            None
+        );
+    }
+
+    #[test]
+    fn changed_lines_with_context() {
+        let old = buffer("1\n2\n3\n4\n5\nold\n7\n8\n9\n10\n");
+        let new = buffer("1\n2\n3\n4\n5\nnew\nadded\n7\n8\n9\n10\n");
+        let diff = unified(&old, "old", &new, "new").unwrap();
+        assert_eq!(
+            diff,
+            "--- old\n+++ new\n@@ -3,7 +3,8 @@\n 3\n 4\n 5\n-old\n+new\n+added\n 7\n 8\n 9\n"
+        );
+    }
+
+    #[test]
+    fn lines_removed_at_the_end() {
+        let diff = unified(&buffer("a\nb\nc\n"), "old", &buffer("a\n"), "new").unwrap();
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,3 +1,1 @@\n a\n-b\n-c\n");
+    }
+
+    #[test]
+    fn binary_buffers_count_changed_bytes() {
+        let old = Buffer {
+            binary: Some(vec![0, 1, 2]),
+            ..Buffer::default()
+        };
+        let new = Buffer {
+            binary: Some(vec![0, 9, 2, 3]),
+            ..Buffer::default()
+        };
+        let diff = unified(&old, "old", &new, "new").unwrap();
+        assert_eq!(diff, "1 bytes differ\n3 bytes in old\n4 bytes in new\n");
+    }
+}
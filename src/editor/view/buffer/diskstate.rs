@@ -0,0 +1,76 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, Metadata};
+use std::hash::Hasher;
+use std::io::{Error, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+//what the file on disk looked like when it was last loaded or saved
+#[derive(Clone)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    //not computed for streamed files, reading them completely is too slow
+    hash: Option<u64>,
+}
+
+impl DiskState {
+    pub fn read(path: &Path, with_hash: bool) -> Result<Self, Error> {
+        let metadata = fs::metadata(path)?;
+        let hash = if with_hash {
+            Some(Self::hash_file(path)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash,
+        })
+    }
+
+    fn hash_file(path: &Path) -> Result<u64, Error> {
+        let mut file = File::open(path)?;
+        let mut hasher = DefaultHasher::new();
+        let mut chunk = vec![0; 64 * 1024];
+        loop {
+            let read = file.read(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+            hasher.write(&chunk[..read]);
+        }
+        Ok(hasher.finish())
+    }
+
+    //the same version of the file, as far as the metadata tells
+    pub fn same_file(&self, other: &Self) -> bool {
+        self.len == other.len && self.modified == other.modified
+    }
+
+    fn same_metadata(&self, metadata: &Metadata) -> bool {
+        self.len == metadata.len() && self.modified == metadata.modified().ok()
+    }
+
+    //false if the file changed since this state was taken.
+    //a file that was only touched keeps its content hash,
+    //then the new metadata is taken over so it isn't hashed again
+    pub fn is_current(&mut self, path: &Path) -> bool {
+        let Ok(metadata) = fs::metadata(path) else {
+            //deleted files have nothing to reload, saving creates them again
+            return true;
+        };
+        if self.same_metadata(&metadata) {
+            return true;
+        }
+        let Some(hash) = self.hash else {
+            return false;
+        };
+        if Self::hash_file(path).is_ok_and(|new_hash| new_hash == hash) {
+            self.modified = metadata.modified().ok();
+            self.len = metadata.len();
+            return true;
+        }
+        false
+    }
+}
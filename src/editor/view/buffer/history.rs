@@ -0,0 +1,249 @@
+use super::super::Location;
+
+//char indices into the buffer text
+#[derive(Clone)]
+pub enum Change {
+    Insert { at: usize, text: String },
+    Remove { at: usize, text: String },
+}
+
+impl Change {
+    pub fn inverse(&self) -> Self {
+        match self {
+            Self::Insert { at, text } => Self::Remove {
+                at: *at,
+                text: text.clone(),
+            },
+            Self::Remove { at, text } => Self::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+        }
+    }
+
+    //a single typed character, runs of these are merged into one undo step
+    fn is_typing(&self) -> bool {
+        match self {
+            Self::Insert { text, .. } => {
+                let mut chars = text.chars();
+                matches!((chars.next(), chars.next()), (Some(ch), None) if ch != '\n')
+            }
+            Self::Remove { .. } => false,
+        }
+    }
+
+    fn start(&self) -> usize {
+        match self {
+            Self::Insert { at, .. } | Self::Remove { at, .. } => *at,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Self::Insert { at, text } => at.saturating_add(text.chars().count()),
+            Self::Remove { at, .. } => *at,
+        }
+    }
+}
+
+//one undo step
+pub struct Transaction {
+    id: usize,
+    pub changes: Vec<Change>,
+    pub before: Location,
+    pub after: Location,
+}
+
+impl Transaction {
+    fn is_typing(&self) -> bool {
+        self.changes.iter().all(Change::is_typing)
+    }
+}
+
+//changes are recorded into `pending` as the buffer is mutated
+//and grouped into a transaction on `commit`.
+//each transaction gets a new id so the saved state can be found again
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    pending: Vec<Change>,
+    next_id: usize,
+    saved_id: Option<usize>,
+}
+
+impl History {
+    pub fn record(&mut self, change: Change) {
+        self.pending.push(change);
+    }
+
+    fn new_id(&mut self) -> usize {
+        self.next_id = self.next_id.saturating_add(1);
+        self.next_id
+    }
+
+    pub fn commit(&mut self, before: Location, after: Location) {
+        self.push(before, after, true);
+    }
+
+    //never merged with typing, for edits like replacements made one by one
+    pub fn commit_separate(&mut self, before: Location, after: Location) {
+        self.push(before, after, false);
+    }
+
+    fn push(&mut self, before: Location, after: Location, merge: bool) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let changes = std::mem::take(&mut self.pending);
+        self.redo_stack.clear();
+        let id = self.new_id();
+
+        let saved_id = self.saved_id;
+        if let Some(last) = self.undo_stack.last_mut() {
+            let is_continuation = merge
+                && last.is_typing()
+                && changes.iter().all(Change::is_typing)
+                && last.after == before
+                && Some(last.id) != saved_id
+                && last.changes.last().map(Change::end) == changes.first().map(Change::start);
+            if is_continuation {
+                last.changes.extend(changes);
+                last.after = after;
+                last.id = id;
+                return;
+            }
+        }
+        self.undo_stack.push(Transaction {
+            id,
+            changes,
+            before,
+            after,
+        });
+    }
+
+    pub fn undo(&mut self) -> Option<&Transaction> {
+        let transaction = self.undo_stack.pop()?;
+        self.redo_stack.push(transaction);
+        self.redo_stack.last()
+    }
+
+    pub fn redo(&mut self) -> Option<&Transaction> {
+        let transaction = self.redo_stack.pop()?;
+        self.undo_stack.push(transaction);
+        self.undo_stack.last()
+    }
+
+    fn current_id(&self) -> Option<usize> {
+        self.undo_stack.last().map(|transaction| transaction.id)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_id = self.current_id();
+    }
+
+    pub fn is_at_saved(&self) -> bool {
+        self.pending.is_empty() && self.current_id() == self.saved_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: usize) -> Location {
+        Location { x, y: 0 }
+    }
+
+    fn type_char(history: &mut History, x: usize, ch: char) {
+        history.record(Change::Insert {
+            at: x,
+            text: ch.to_string(),
+        });
+        history.commit(at(x), at(x.saturating_add(1)));
+    }
+
+    #[test]
+    fn typing_merges_into_one_step() {
+        let mut history = History::default();
+        type_char(&mut history, 0, 'a');
+        type_char(&mut history, 1, 'b');
+        type_char(&mut history, 2, 'c');
+        let transaction = history.undo().unwrap();
+        assert_eq!(transaction.changes.len(), 3);
+        assert_eq!(transaction.before, at(0));
+        assert_eq!(transaction.after, at(3));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn newline_and_separate_commits_are_not_merged() {
+        let mut history = History::default();
+        type_char(&mut history, 0, 'a');
+        type_char(&mut history, 1, '\n');
+        history.record(Change::Insert {
+            at: 2,
+            text: "b".to_string(),
+        });
+        history.commit_separate(at(2), at(3));
+        assert_eq!(history.undo().unwrap().changes.len(), 1);
+        assert_eq!(history.undo().unwrap().changes.len(), 1);
+        assert_eq!(history.undo().unwrap().changes.len(), 1);
+    }
+
+    #[test]
+    fn merge_never_crosses_the_save_point() {
+        let mut history = History::default();
+        type_char(&mut history, 0, 'a');
+        history.mark_saved();
+        type_char(&mut history, 1, 'b');
+        type_char(&mut history, 2, 'c');
+        assert!(!history.is_at_saved());
+        assert_eq!(history.undo().unwrap().changes.len(), 2);
+        assert!(history.is_at_saved());
+        assert_eq!(history.undo().unwrap().changes.len(), 1);
+        assert!(!history.is_at_saved());
+    }
+
+    #[test]
+    fn undo_and_redo_to_the_save_point() {
+        let mut history = History::default();
+        assert!(history.is_at_saved());
+        type_char(&mut history, 0, 'a');
+        assert!(!history.is_at_saved());
+        history.undo();
+        assert!(history.is_at_saved());
+        history.redo();
+        assert!(!history.is_at_saved());
+        history.mark_saved();
+        assert!(history.is_at_saved());
+        history.undo();
+        assert!(!history.is_at_saved());
+        history.redo();
+        assert!(history.is_at_saved());
+    }
+
+    #[test]
+    fn pending_changes_count_as_modified() {
+        let mut history = History::default();
+        history.record(Change::Remove {
+            at: 0,
+            text: "a".to_string(),
+        });
+        assert!(!history.is_at_saved());
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut history = History::default();
+        type_char(&mut history, 0, 'a');
+        history.record(Change::Remove {
+            at: 0,
+            text: "a".to_string(),
+        });
+        history.commit(at(1), at(0));
+        assert!(history.undo().is_some());
+        type_char(&mut history, 1, 'b');
+        assert!(history.redo().is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use super::super::encoding::{Encoding, Utf8Stats};
+use std::fs::File;
+use std::io::{Error, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+//start offset of every line found so far
+#[derive(Default)]
+struct LineIndex {
+    offsets: Vec<u64>,
+    complete: bool,
+}
+
+//a file too big to be loaded up front.
+//lines are indexed by a background thread and only read and decoded when asked for
+pub struct StreamedFile {
+    //only ever seeked by the ui thread
+    file: File,
+    len: u64,
+    index: Arc<Mutex<LineIndex>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl StreamedFile {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let index = Arc::new(Mutex::new(LineIndex {
+            offsets: vec![0],
+            complete: false,
+        }));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        //a handle of its own, a cloned one would share the offset `line` seeks
+        let indexer_file = File::open(path)?;
+        let indexer_index = Arc::clone(&index);
+        let indexer_cancel = Arc::clone(&cancel);
+        thread::spawn(move || {
+            Self::build_index(indexer_file, len, &indexer_index, &indexer_cancel);
+        });
+
+        Ok(Self {
+            file,
+            len,
+            index,
+            cancel,
+        })
+    }
+
+    fn build_index(mut file: File, len: u64, index: &Mutex<LineIndex>, cancel: &AtomicBool) {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut position: u64 = 0;
+        let mut found = Vec::new();
+        while !cancel.load(Ordering::Relaxed) {
+            let read = match file.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            for (offset, byte) in chunk.iter().take(read).enumerate() {
+                if *byte == b'\n' {
+                    let offset = u64::try_from(offset).unwrap_or_default();
+                    found.push(position.saturating_add(offset).saturating_add(1));
+                }
+            }
+            position = position.saturating_add(u64::try_from(read).unwrap_or_default());
+            Self::lock(index).offsets.append(&mut found);
+        }
+        let mut index = Self::lock(index);
+        //a final newline doesn't start another line
+        if index.offsets.last() == Some(&len) {
+            index.offsets.pop();
+        }
+        index.complete = true;
+    }
+
+    //the indexer never panics while holding the lock, a poisoned index is still usable
+    fn lock(index: &Mutex<LineIndex>) -> MutexGuard<'_, LineIndex> {
+        index
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        !Self::lock(&self.index).complete
+    }
+
+    //lines indexed so far, only final once indexing is done
+    pub fn line_count(&self) -> usize {
+        let index = Self::lock(&self.index);
+        if index.complete {
+            index.offsets.len()
+        } else {
+            index.offsets.len().saturating_sub(1)
+        }
+    }
+
+    fn line_range(&self, y: usize) -> Option<(u64, u64)> {
+        let index = Self::lock(&self.index);
+        let start = *index.offsets.get(y)?;
+        match index.offsets.get(y.saturating_add(1)) {
+            Some(end) => Some((start, *end)),
+            None if index.complete => Some((start, self.len)),
+            None => None,
+        }
+    }
+
+    //without the line ending
+    pub fn line(&self, y: usize) -> Option<String> {
+        let (start, end) = self.line_range(y)?;
+        let len = usize::try_from(end.saturating_sub(start)).ok()?;
+        let mut bytes = vec![0; len];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(start)).ok()?;
+        file.read_exact(&mut bytes).ok()?;
+        let mut line = Encoding::Utf8.decode(&bytes, &mut Utf8Stats::default());
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Some(line)
+    }
+
+    pub fn copy_to<W: std::io::Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(0))?;
+        std::io::copy(&mut file, writer)?;
+        Ok(())
+    }
+}
+
+impl Drop for StreamedFile {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+//bytes read so far by a load, shared with the thread doing it
+#[derive(Clone, Default)]
+pub struct LoadProgress {
+    read: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl LoadProgress {
+    //counting starts over for every file opened
+    pub fn reader(&self, file: File) -> ProgressReader {
+        self.read.store(0, Ordering::Relaxed);
+        ProgressReader {
+            file,
+            progress: self.clone(),
+        }
+    }
+}
+
+pub struct ProgressReader {
+    file: File,
+    progress: LoadProgress,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.progress.cancel.load(Ordering::Relaxed) {
+            return Err(Error::other("loading cancelled"));
+        }
+        let read = self.file.read(buf)?;
+        self.progress
+            .read
+            .fetch_add(u64::try_from(read).unwrap_or_default(), Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+//a streamed file read completely by a background thread, so the ui keeps going
+//while a file of several gigabytes is loaded for editing
+pub struct FullLoad<T> {
+    progress: LoadProgress,
+    len: u64,
+    result: Receiver<Result<T, Error>>,
+}
+
+impl<T: Send + 'static> FullLoad<T> {
+    pub fn start<F>(len: u64, load: F) -> Self
+    where
+        F: FnOnce(&LoadProgress) -> Result<T, Error> + Send + 'static,
+    {
+        let progress = LoadProgress::default();
+        let (sender, result) = mpsc::channel();
+        let loader_progress = progress.clone();
+        thread::spawn(move || {
+            //nobody waits for the result anymore if sending fails
+            let _ = sender.send(load(&loader_progress));
+        });
+        Self {
+            progress,
+            len,
+            result,
+        }
+    }
+
+    pub fn percent(&self) -> u64 {
+        let read = self.progress.read.load(Ordering::Relaxed).min(self.len);
+        read.saturating_mul(100)
+            .checked_div(self.len)
+            .unwrap_or(100)
+    }
+
+    //None while the load is still going
+    pub fn poll(&self) -> Option<Result<T, Error>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::other("loading stopped"))),
+        }
+    }
+}
+
+impl<T> Drop for FullLoad<T> {
+    fn drop(&mut self) {
+        self.progress.cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,194 @@
+use super::atomicwrite::{sync_parent, write_atomic};
+use crate::editor::config::Config;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+//locks taken by this process, a lock file with our pid that isn't in here
+//was left by a crashed process that had the same pid
+static HELD_LOCKS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn held_locks() -> MutexGuard<'static, Vec<PathBuf>> {
+    HELD_LOCKS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+//unsaved edits of a document, written to $XDG_STATE_HOME/mim/swap so they survive a crash.
+//files are named after the absolute path of the document with "/" replaced by "%",
+//the ".lock" file next to the ".swp" file holds the pid of the instance editing the document
+pub struct SwapFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    //the swap file exists, written by us or left behind by an earlier session
+    on_disk: bool,
+    //left behind by an earlier session, kept untouched until it is recovered or discarded
+    stale: bool,
+    //revision of the buffer written last, None if nothing was written since the last discard
+    written: Option<usize>,
+}
+
+impl SwapFile {
+    fn paths(filename: &str) -> Result<(PathBuf, PathBuf), Error> {
+        let dir = Config::state_dir()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no state directory"))?
+            .join("swap");
+        let name = std::path::absolute(filename)?
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "%");
+        Ok((
+            dir.join(format!("{name}.swp")),
+            dir.join(format!("{name}.lock")),
+        ))
+    }
+
+    //fails if another running instance is editing the same file
+    pub fn open(filename: &str) -> Result<Self, Error> {
+        let (path, lock_path) = Self::paths(filename)?;
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+        Self::lock(&lock_path)?;
+        let stale = path.exists();
+        Ok(Self {
+            path,
+            lock_path,
+            on_disk: stale,
+            stale,
+            written: None,
+        })
+    }
+
+    fn lock(lock_path: &Path) -> Result<(), Error> {
+        //a second try after removing the lock of a crashed instance
+        for _ in 0..2 {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(lock_path)
+            {
+                Ok(mut file) => {
+                    held_locks().push(lock_path.to_path_buf());
+                    return write!(file, "{}", std::process::id());
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if held_locks().iter().any(|held| held == lock_path) {
+                        return Err(Error::new(
+                            ErrorKind::AlreadyExists,
+                            "the file is already open in another buffer",
+                        ));
+                    }
+                    let owner = fs::read_to_string(lock_path)
+                        .ok()
+                        .and_then(|pid| pid.trim().parse::<u32>().ok());
+                    if let Some(pid) = owner.filter(|pid| is_running(*pid)) {
+                        return Err(Error::new(
+                            ErrorKind::AlreadyExists,
+                            format!("the file is open in another instance (pid {pid})"),
+                        ));
+                    }
+                    match fs::remove_file(lock_path) {
+                        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                        _ => {}
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(Error::new(
+            ErrorKind::AlreadyExists,
+            "couldnot lock the swap file",
+        ))
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    pub fn read(&self) -> Result<Vec<u8>, Error> {
+        fs::read(&self.path)
+    }
+
+    //the stale contents were taken over, from now on the swap file is ours to overwrite
+    pub fn take_over(&mut self) {
+        self.stale = false;
+    }
+
+    //true if `revision` of the buffer is what the swap file holds
+    pub fn is_written(&self, revision: usize) -> bool {
+        self.written == Some(revision)
+    }
+
+    pub fn write<F>(&mut self, revision: usize, write: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+    {
+        if self.stale {
+            return Ok(());
+        }
+        write_atomic(&self.path, write)?;
+        self.on_disk = true;
+        self.written = Some(revision);
+        sync_parent(&self.path)
+    }
+
+    pub fn discard(&mut self) -> Result<(), Error> {
+        if self.on_disk {
+            match fs::remove_file(&self.path) {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        self.on_disk = false;
+        self.stale = false;
+        self.written = None;
+        Ok(())
+    }
+}
+
+impl Drop for SwapFile {
+    //only the lock goes, the swap file is removed by `discard` once the edits are saved
+    //or the user quits. a crash or a buffer dropped along the way leaves it for recovery
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lock_path);
+        held_locks().retain(|held| *held != self.lock_path);
+    }
+}
+
+//swap files hold unsaved document contents, only the user may read them
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> Result<(), Error> {
+    fs::create_dir_all(dir)
+}
+
+//a lock with our own pid that isn't in `HELD_LOCKS` was left by a crashed process
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    pid != std::process::id() && Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> bool {
+    pid != std::process::id()
+        && std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+//no way to tell, better not to take the lock of a running instance
+#[cfg(not(unix))]
+fn is_running(pid: u32) -> bool {
+    pid != std::process::id()
+}
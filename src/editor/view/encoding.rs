@@ -0,0 +1,412 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+//bytes that can't be decoded are kept as private use characters
+//so they can be written back unchanged:
+//invalid byte b             => U+10FE00 + b
+//unpaired utf-16 surrogate s => U+10F000 + (s - 0xD800)
+//real characters in these ranges are kept as the escaped bytes they are encoded as,
+//so every escape in the text stands for what was in the file
+const INVALID_BYTE_BASE: u32 = 0x0010_FE00;
+const SURROGATE_BASE: u32 = 0x0010_F000;
+const SURROGATE_START: u16 = 0xD800;
+const SURROGATE_END: u16 = 0xDFFF;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+//0x80..=0x9F in windows-1252, the undefined ones map to the C1 control with the same value
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16LeBom,
+    Utf16Be,
+    Utf16BeBom,
+    Windows1252,
+}
+
+//what decoding a chunk of utf-8 found, used to fall back to windows-1252
+#[derive(Default)]
+pub struct Utf8Stats {
+    pub invalid_bytes: usize,
+    pub multibyte_chars: usize,
+}
+
+impl Utf8Stats {
+    //latin-1 text almost never forms valid multibyte sequences,
+    //utf-8 text with a few broken bytes does
+    pub fn looks_like_windows_1252(&self) -> bool {
+        self.invalid_bytes > 0 && self.multibyte_chars == 0
+    }
+}
+
+fn escape_byte(byte: u8) -> char {
+    char::from_u32(INVALID_BYTE_BASE.saturating_add(u32::from(byte))).unwrap_or('\u{FFFD}')
+}
+
+fn escape_surrogate(unit: u16) -> char {
+    let offset = u32::from(unit.saturating_sub(SURROGATE_START));
+    char::from_u32(SURROGATE_BASE.saturating_add(offset)).unwrap_or('\u{FFFD}')
+}
+
+fn unescape_byte(ch: char) -> Option<u8> {
+    u32::from(ch)
+        .checked_sub(INVALID_BYTE_BASE)
+        .and_then(|byte| u8::try_from(byte).ok())
+}
+
+fn unescape_surrogate(ch: char) -> Option<u16> {
+    u32::from(ch)
+        .checked_sub(SURROGATE_BASE)
+        .and_then(|offset| u16::try_from(offset).ok())
+        .and_then(|offset| offset.checked_add(SURROGATE_START))
+        .filter(|unit| *unit <= SURROGATE_END)
+}
+
+//escaped bytes or surrogates are rendered as replacement characters
+pub fn is_escaped(ch: char) -> bool {
+    unescape_byte(ch).is_some() || unescape_surrogate(ch).is_some()
+}
+
+//NUL bytes never show up in text, neither do many invalid utf-8 sequences.
+//`head` is the start of a file without a byte order mark or utf-16 pattern
+pub fn looks_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    let mut stats = Utf8Stats::default();
+    decode_utf8(head, &mut stats);
+    //windows-1252 text stays well below this
+    stats.invalid_bytes.saturating_mul(10) > head.len().saturating_mul(3)
+}
+
+impl Encoding {
+    //detection that only needs the start of the file: byte order marks and
+    //the NUL pattern of utf-16 text. None means utf-8 or windows-1252,
+    //which can only be told apart after decoding.
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(UTF8_BOM) {
+            return Some(Self::Utf8Bom);
+        }
+        if head.starts_with(UTF16LE_BOM) {
+            return Some(Self::Utf16LeBom);
+        }
+        if head.starts_with(UTF16BE_BOM) {
+            return Some(Self::Utf16BeBom);
+        }
+        #[allow(clippy::integer_division)]
+        let pairs = head.len() / 2;
+        if pairs == 0 {
+            return None;
+        }
+        let (mut even_nuls, mut odd_nuls) = (0_usize, 0_usize);
+        for pair in head.chunks_exact(2) {
+            if pair[0] == 0 {
+                even_nuls = even_nuls.saturating_add(1);
+            }
+            if pair[1] == 0 {
+                odd_nuls = odd_nuls.saturating_add(1);
+            }
+        }
+        //mostly ascii text: every other byte is NUL
+        #[allow(clippy::integer_division)]
+        let threshold = pairs.saturating_mul(2) / 5;
+        if odd_nuls > threshold && even_nuls.saturating_mul(10) < odd_nuls {
+            Some(Self::Utf16Le)
+        } else if even_nuls > threshold && odd_nuls.saturating_mul(10) < even_nuls {
+            Some(Self::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8Bom => UTF8_BOM,
+            Self::Utf16LeBom => UTF16LE_BOM,
+            Self::Utf16BeBom => UTF16BE_BOM,
+            Self::Utf8 | Self::Utf16Le | Self::Utf16Be | Self::Windows1252 => &[],
+        }
+    }
+
+    pub fn is_utf16(self) -> bool {
+        matches!(
+            self,
+            Self::Utf16Le | Self::Utf16LeBom | Self::Utf16Be | Self::Utf16BeBom
+        )
+    }
+
+    //`bytes` must not contain the byte order mark
+    pub fn decode(self, bytes: &[u8], stats: &mut Utf8Stats) -> String {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => decode_utf8(bytes, stats),
+            Self::Utf16Le | Self::Utf16LeBom | Self::Utf16Be | Self::Utf16BeBom => {
+                decode_utf16(bytes, self)
+            }
+            Self::Windows1252 => bytes.iter().map(|byte| decode_1252(*byte)).collect(),
+        }
+    }
+
+    //for text that didn't come from decoding or a buffer, like typed text
+    pub fn escape(self, text: &str) -> Cow<'_, str> {
+        if !text.chars().any(is_escaped) {
+            return Cow::Borrowed(text);
+        }
+        let mut result = String::with_capacity(text.len());
+        for ch in text.chars() {
+            self.push_char(ch, &mut result);
+        }
+        Cow::Owned(result)
+    }
+
+    fn push_char(self, ch: char, out: &mut String) {
+        if !is_escaped(ch) {
+            out.push(ch);
+            return;
+        }
+        match self {
+            Self::Utf8 | Self::Utf8Bom => {
+                let mut buf = [0; 4];
+                out.extend(ch.encode_utf8(&mut buf).bytes().map(escape_byte));
+            }
+            Self::Utf16Le | Self::Utf16LeBom => {
+                let mut buf = [0; 2];
+                for unit in ch.encode_utf16(&mut buf) {
+                    out.extend(unit.to_le_bytes().map(escape_byte));
+                }
+            }
+            Self::Utf16Be | Self::Utf16BeBom => {
+                let mut buf = [0; 2];
+                for unit in ch.encode_utf16(&mut buf) {
+                    out.extend(unit.to_be_bytes().map(escape_byte));
+                }
+            }
+            //not in windows-1252, saving fails instead of writing something else
+            Self::Windows1252 => out.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+
+    //the byte order mark is not included
+    pub fn encode(self, text: &str, out: &mut Vec<u8>) -> Result<(), Error> {
+        for ch in text.chars() {
+            if let Some(byte) = unescape_byte(ch) {
+                out.push(byte);
+                continue;
+            }
+            match self {
+                Self::Utf8 | Self::Utf8Bom => {
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+                Self::Utf16Le | Self::Utf16LeBom => encode_utf16(ch, out, u16::to_le_bytes),
+                Self::Utf16Be | Self::Utf16BeBom => encode_utf16(ch, out, u16::to_be_bytes),
+                Self::Windows1252 => out.push(encode_1252(ch).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{ch} cannot be saved as {self}"),
+                    )
+                })?),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_utf8(mut bytes: &[u8], stats: &mut Utf8Stats) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    loop {
+        let (valid, invalid) = match std::str::from_utf8(bytes) {
+            Ok(valid) => (valid, None),
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                //always valid, checked by from_utf8
+                let valid = std::str::from_utf8(valid).unwrap_or_default();
+                let invalid_len = err.error_len().unwrap_or(rest.len());
+                (valid, Some(rest.split_at(invalid_len)))
+            }
+        };
+        if valid.chars().any(is_escaped) {
+            for ch in valid.chars() {
+                Encoding::Utf8.push_char(ch, &mut result);
+            }
+        } else {
+            result.push_str(valid);
+        }
+        stats.multibyte_chars = stats
+            .multibyte_chars
+            .saturating_add(valid.chars().filter(|ch| !ch.is_ascii()).count());
+        let Some((invalid, rest)) = invalid else {
+            break;
+        };
+        stats.invalid_bytes = stats.invalid_bytes.saturating_add(invalid.len());
+        result.extend(invalid.iter().map(|byte| escape_byte(*byte)));
+        bytes = rest;
+    }
+    result
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding) -> String {
+    let from_bytes = match encoding {
+        Encoding::Utf16Be | Encoding::Utf16BeBom => u16::from_be_bytes,
+        _ => u16::from_le_bytes,
+    };
+    let chunks = bytes.chunks_exact(2);
+    let trailing = chunks.remainder();
+    let units = chunks.map(|pair| from_bytes([pair[0], pair[1]]));
+    let mut result = String::with_capacity(bytes.len());
+    for decoded in char::decode_utf16(units) {
+        match decoded {
+            Ok(ch) => encoding.push_char(ch, &mut result),
+            Err(err) => result.push(escape_surrogate(err.unpaired_surrogate())),
+        }
+    }
+    result.extend(trailing.iter().map(|byte| escape_byte(*byte)));
+    result
+}
+
+fn encode_utf16(ch: char, out: &mut Vec<u8>, to_bytes: fn(u16) -> [u8; 2]) {
+    if let Some(unit) = unescape_surrogate(ch) {
+        out.extend_from_slice(&to_bytes(unit));
+        return;
+    }
+    let mut buf = [0; 2];
+    for unit in ch.encode_utf16(&mut buf) {
+        out.extend_from_slice(&to_bytes(*unit));
+    }
+}
+
+fn decode_1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252[usize::from(byte.saturating_sub(0x80))],
+        _ => char::from(byte),
+    }
+}
+
+fn encode_1252(ch: char) -> Option<u8> {
+    if let Some(index) = WINDOWS_1252.iter().position(|mapped| *mapped == ch) {
+        return u8::try_from(index)
+            .ok()
+            .map(|index| index.saturating_add(0x80));
+    }
+    u8::try_from(u32::from(ch))
+        .ok()
+        .filter(|byte| !(0x80..=0x9F).contains(byte))
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 BOM",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16LeBom => "UTF-16LE BOM",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf16BeBom => "UTF-16BE BOM",
+            Self::Windows1252 => "Windows-1252",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(encoding: Encoding, bytes: &[u8]) -> Vec<u8> {
+        let text = encoding.decode(bytes, &mut Utf8Stats::default());
+        let mut out = Vec::new();
+        encoding.encode(&text, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn invalid_utf8_is_kept() {
+        let bytes = b"a\xffb\xc3(\xe2\x82 end\xf4";
+        assert_eq!(round_trip(Encoding::Utf8, bytes), bytes);
+    }
+
+    #[test]
+    fn real_characters_in_the_escape_ranges_are_kept() {
+        let text = "\u{10FE41} \u{10F123} \u{10FEFF}\u{10F7FF}";
+        let bytes = text.as_bytes();
+        assert_eq!(round_trip(Encoding::Utf8, bytes), bytes);
+
+        let mut utf16 = Vec::new();
+        Encoding::Utf16Le.encode(text, &mut utf16).unwrap();
+        assert_eq!(round_trip(Encoding::Utf16Le, &utf16), utf16);
+    }
+
+    #[test]
+    fn mixed_escapes_and_real_characters_are_kept() {
+        let mut bytes = "\u{10FEFF}".as_bytes().to_vec();
+        bytes.extend_from_slice(b"\xfe\xff");
+        bytes.extend_from_slice("\u{10F000}".as_bytes());
+        assert_eq!(round_trip(Encoding::Utf8, &bytes), bytes);
+    }
+
+    #[test]
+    fn unpaired_surrogates_are_kept() {
+        //lone high surrogate, lone low surrogate, 'a', a trailing odd byte
+        let le = [0x00, 0xD8, 0x00, 0xDC, 0x61, 0x00, 0x7F];
+        assert_eq!(round_trip(Encoding::Utf16Le, &le), le);
+        let be = [0xD8, 0x3D, 0x00, 0x61, 0xDF, 0xFF];
+        assert_eq!(round_trip(Encoding::Utf16Be, &be), be);
+    }
+
+    #[test]
+    fn typed_characters_in_the_escape_ranges_are_kept() {
+        let escaped = Encoding::Utf8.escape("x\u{10FE41}");
+        let mut out = Vec::new();
+        Encoding::Utf8.encode(&escaped, &mut out).unwrap();
+        assert_eq!(out, "x\u{10FE41}".as_bytes());
+        assert!(matches!(Encoding::Utf8.escape("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn windows_1252_round_trips_every_byte() {
+        let bytes: Vec<u8> = (0..=u8::MAX).collect();
+        assert_eq!(round_trip(Encoding::Windows1252, &bytes), bytes);
+        let text = Encoding::Windows1252.decode(b"\x80\xe9", &mut Utf8Stats::default());
+        assert_eq!(text, "\u{20AC}\u{e9}");
+    }
+
+    #[test]
+    fn detects_byte_order_marks_and_utf16() {
+        assert_eq!(Encoding::detect(b"\xef\xbb\xbfa"), Some(Encoding::Utf8Bom));
+        assert_eq!(
+            Encoding::detect(b"\xff\xfea\x00"),
+            Some(Encoding::Utf16LeBom)
+        );
+        assert_eq!(
+            Encoding::detect(b"h\x00i\x00\n\x00"),
+            Some(Encoding::Utf16Le)
+        );
+        assert_eq!(
+            Encoding::detect(b"\x00h\x00i\x00\n"),
+            Some(Encoding::Utf16Be)
+        );
+        assert_eq!(Encoding::detect(b"plain text"), None);
+    }
+
+    #[test]
+    fn latin1_text_falls_back_to_windows_1252() {
+        let mut stats = Utf8Stats::default();
+        Encoding::Utf8.decode(b"caf\xe9 cr\xe8me", &mut stats);
+        assert!(stats.looks_like_windows_1252());
+        let mut stats = Utf8Stats::default();
+        Encoding::Utf8.decode("café \u{10FE41}".as_bytes(), &mut stats);
+        assert!(!stats.looks_like_windows_1252());
+    }
+}
@@ -0,0 +1,54 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum LineNumbers {
+    #[default]
+    Off,
+    Absolute,
+    //distance to the cursor line, which shows 0
+    Relative,
+    //distance to the cursor line, which shows its own number
+    Hybrid,
+}
+
+impl FromStr for LineNumbers {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "off" | "false" | "no" => Ok(Self::Off),
+            "absolute" | "true" | "yes" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => Err(format!("unknown line numbers `{value}`")),
+        }
+    }
+}
+
+//at least this many digits so the text doesn't move for every new digit of small files
+const MIN_DIGITS: usize = 3;
+
+impl LineNumbers {
+    //digits of the biggest line number and a blank before the text, 0 if off
+    pub fn width(self, line_count: usize) -> usize {
+        if self == Self::Off {
+            return 0;
+        }
+        let digits = line_count.max(1).ilog10().saturating_add(1);
+        usize::try_from(digits)
+            .unwrap_or(MIN_DIGITS)
+            .max(MIN_DIGITS)
+            .saturating_add(1)
+    }
+
+    //what is shown next to line `y`, counting from 0
+    pub fn label(self, y: usize, cursor_y: usize) -> String {
+        let number = match self {
+            Self::Off => return String::new(),
+            Self::Hybrid if y == cursor_y => y.saturating_add(1),
+            Self::Absolute => y.saturating_add(1),
+            Self::Relative | Self::Hybrid => y.abs_diff(cursor_y),
+        };
+        number.to_string()
+    }
+}
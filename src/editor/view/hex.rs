@@ -0,0 +1,65 @@
+use std::fmt::Write;
+
+pub const BYTES_PER_ROW: usize = 16;
+const HALF_ROW: usize = 8;
+//"00000000  "
+const OFFSET_WIDTH: usize = 10;
+
+//cursor of the hex view, overwriting a byte sets the high nibble first
+#[derive(Copy, Clone, Default)]
+pub struct HexCursor {
+    pub offset: usize,
+    pub low_nibble: bool,
+}
+
+impl HexCursor {
+    #[allow(clippy::integer_division)]
+    pub fn row(self) -> usize {
+        self.offset / BYTES_PER_ROW
+    }
+
+    //screen column of the nibble under the cursor
+    pub fn column(self) -> usize {
+        let index = self.offset % BYTES_PER_ROW;
+        //extra gap between the two groups of eight
+        let gap = usize::from(index >= HALF_ROW);
+        OFFSET_WIDTH
+            .saturating_add(index.saturating_mul(3))
+            .saturating_add(gap)
+            .saturating_add(usize::from(self.low_nibble))
+    }
+}
+
+//"00000010  48 65 6c 6c 6f 0a 00 00  00 00 00 00 00 00 00 00  |Hello...........|"
+pub fn render_row(row_bytes: &[u8], row: usize) -> String {
+    let mut result = format!("{:08x}  ", row.saturating_mul(BYTES_PER_ROW));
+    for index in 0..BYTES_PER_ROW {
+        match row_bytes.get(index) {
+            Some(byte) => {
+                let _ = write!(result, "{byte:02x} ");
+            }
+            None => result.push_str("   "),
+        }
+        if index.saturating_add(1) == HALF_ROW {
+            result.push(' ');
+        }
+    }
+    result.push_str(" |");
+    result.extend(row_bytes.iter().map(|byte| {
+        if byte.is_ascii_graphic() || *byte == b' ' {
+            char::from(*byte)
+        } else {
+            '.'
+        }
+    }));
+    result.push('|');
+    result
+}
+
+pub fn set_nibble(byte: u8, nibble: u8, low_nibble: bool) -> u8 {
+    if low_nibble {
+        (byte & 0xf0) | (nibble & 0x0f)
+    } else {
+        (byte & 0x0f) | nibble.wrapping_shl(4)
+    }
+}
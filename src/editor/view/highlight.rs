@@ -0,0 +1,153 @@
+use crate::editor::filetype::FileType;
+use ropey::Rope;
+use std::ops::Range;
+
+mod markdown;
+mod syntax;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Comment,
+    Number,
+    Type,
+}
+
+//`range` is in bytes of the line, tokens of a line are sorted and don't overlap
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+//what a line leaves open for the lines after it
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Normal,
+    //nesting depth, only rust nests block comments
+    BlockComment(usize),
+    String {
+        close: &'static str,
+        escapes: bool,
+    },
+    //rust raw string, closed by '"' and this many '#'
+    RawString(usize),
+    //markdown fenced code block
+    CodeBlock,
+}
+
+//a lexer only ever sees one line, anything that goes on past its end is kept in `State`
+pub trait Lexer {
+    fn lex(&self, line: &str, state: State, tokens: &mut Vec<Token>) -> State;
+}
+
+fn lexer(file_type: FileType) -> Option<&'static dyn Lexer> {
+    match file_type {
+        FileType::Rust => Some(&syntax::RUST),
+        FileType::C => Some(&syntax::C),
+        FileType::Python => Some(&syntax::PYTHON),
+        FileType::Json => Some(&syntax::JSON),
+        FileType::Toml => Some(&syntax::TOML),
+        FileType::Markdown => Some(&markdown::Markdown),
+        FileType::Text => None,
+    }
+}
+
+struct HighlightedLine {
+    start: State,
+    end: State,
+    tokens: Vec<Token>,
+}
+
+//tokens of the lines up to the last one drawn, lexed on demand.
+//an edit only drops the lines it touched, the lines after it are
+//lexed again only if the state they start in has changed
+#[derive(Default)]
+pub struct Highlighter {
+    lexer: Option<&'static dyn Lexer>,
+    lines: Vec<Option<HighlightedLine>>,
+    //lines before this one are up to date
+    valid: usize,
+}
+
+impl Highlighter {
+    pub fn new(file_type: FileType) -> Self {
+        Self {
+            lexer: lexer(file_type),
+            ..Self::default()
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.valid = 0;
+    }
+
+    //line `y` lost `removed` line breaks and got `added` new ones
+    pub fn edit(&mut self, y: usize, removed: usize, added: usize) {
+        if y >= self.lines.len() {
+            return;
+        }
+        let end = y
+            .saturating_add(removed)
+            .saturating_add(1)
+            .min(self.lines.len());
+        self.lines.splice(
+            y..end,
+            std::iter::repeat_with(|| None).take(added.saturating_add(1)),
+        );
+        self.valid = self.valid.min(y);
+    }
+
+    //brings the lines before `until` up to date
+    pub fn update(&mut self, text: &Rope, until: usize) {
+        let Some(lexer) = self.lexer else {
+            return;
+        };
+        let until = until.min(text.len_lines().saturating_sub(1));
+        let mut state = self
+            .valid
+            .checked_sub(1)
+            .and_then(|y| self.lines.get(y))
+            .and_then(Option::as_ref)
+            .map_or(State::Normal, |line| line.end);
+        for y in self.valid..until {
+            let cached = self.lines.get(y).and_then(Option::as_ref);
+            if let Some(cached) = cached.filter(|cached| cached.start == state) {
+                state = cached.end;
+                continue;
+            }
+            let mut line = text.line(y).to_string();
+            line.pop(); // '\n'
+            if line.ends_with('\r') {
+                line.pop();
+            }
+            let mut tokens = Vec::new();
+            let end = lexer.lex(&line, state, &mut tokens);
+            let highlighted = Some(HighlightedLine {
+                start: state,
+                end,
+                tokens,
+            });
+            match self.lines.get_mut(y) {
+                Some(entry) => *entry = highlighted,
+                None => self.lines.push(highlighted),
+            }
+            state = end;
+        }
+        self.valid = self.valid.max(until);
+    }
+
+    //None if the line wasn't brought up to date
+    pub fn tokens(&self, y: usize) -> Option<&[Token]> {
+        if y >= self.valid {
+            return None;
+        }
+        self.lines
+            .get(y)?
+            .as_ref()
+            .map(|line| line.tokens.as_slice())
+    }
+}
@@ -0,0 +1,130 @@
+use super::{Lexer, State, Token, TokenKind};
+
+//headings, fenced and inline code, quotes, list markers, emphasis, links and html comments
+pub struct Markdown;
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+//"- ", "* ", "+ ", "1. " or "1) "
+fn list_marker_len(line: &str) -> Option<usize> {
+    if line.starts_with(['-', '*', '+']) {
+        return line[1..].starts_with(' ').then_some(1);
+    }
+    let digits = line.len().saturating_sub(
+        line.trim_start_matches(|char: char| char.is_ascii_digit())
+            .len(),
+    );
+    let rest = &line[digits..];
+    (digits > 0 && (rest.starts_with(". ") || rest.starts_with(") ")))
+        .then_some(digits.saturating_add(1))
+}
+
+//html comments are the only thing that goes on past the end of a line
+fn lex_inline(line: &str, from: usize, tokens: &mut Vec<Token>) -> State {
+    let mut push = |kind, range| tokens.push(Token { kind, range });
+    let mut at = from;
+    while let Some(char) = line[at..].chars().next() {
+        let rest = &line[at..];
+        if rest.starts_with("<!--") {
+            let Some(end) = rest.find("-->") else {
+                push(TokenKind::Comment, at..line.len());
+                return State::BlockComment(1);
+            };
+            let end = at.saturating_add(end).saturating_add(3);
+            push(TokenKind::Comment, at..end);
+            at = end;
+            continue;
+        }
+        let (kind, close) = match char {
+            '`' => (TokenKind::String, "`"),
+            '*' if rest.starts_with("**") => (TokenKind::Type, "**"),
+            '*' => (TokenKind::Type, "*"),
+            '[' => (TokenKind::Keyword, "]"),
+            _ => {
+                at = at.saturating_add(char.len_utf8());
+                continue;
+            }
+        };
+        let open = if close == "]" { 1 } else { close.len() };
+        let Some(end) = rest[open..].find(close) else {
+            at = at.saturating_add(open);
+            continue;
+        };
+        let end = at
+            .saturating_add(open)
+            .saturating_add(end)
+            .saturating_add(close.len());
+        push(kind, at..end);
+        at = end;
+        //the target of a link
+        if close == "]" && line[at..].starts_with('(') {
+            if let Some(target) = line[at..].find(')') {
+                let target = at.saturating_add(target).saturating_add(1);
+                push(TokenKind::String, at..target);
+                at = target;
+            }
+        }
+    }
+    State::Normal
+}
+
+impl Lexer for Markdown {
+    fn lex(&self, line: &str, state: State, tokens: &mut Vec<Token>) -> State {
+        let whole = |kind| Token {
+            kind,
+            range: 0..line.len(),
+        };
+        match state {
+            State::CodeBlock => {
+                tokens.push(whole(TokenKind::String));
+                return if is_fence(line) {
+                    State::Normal
+                } else {
+                    State::CodeBlock
+                };
+            }
+            State::BlockComment(_) => {
+                let Some(end) = line.find("-->") else {
+                    tokens.push(whole(TokenKind::Comment));
+                    return state;
+                };
+                let end = end.saturating_add(3);
+                tokens.push(Token {
+                    kind: TokenKind::Comment,
+                    range: 0..end,
+                });
+                return lex_inline(line, end, tokens);
+            }
+            _ => {}
+        }
+        let trimmed = line.trim_start();
+        let indent = line.len().saturating_sub(trimmed.len());
+        if is_fence(line) {
+            tokens.push(whole(TokenKind::String));
+            return State::CodeBlock;
+        }
+        if trimmed.starts_with('#') {
+            tokens.push(whole(TokenKind::Keyword));
+            return State::Normal;
+        }
+        if trimmed.starts_with('>') {
+            tokens.push(whole(TokenKind::Comment));
+            return State::Normal;
+        }
+        let from = match list_marker_len(trimmed) {
+            Some(len) => {
+                let end = indent.saturating_add(len);
+                tokens.push(Token {
+                    kind: TokenKind::Number,
+                    range: indent..end,
+                });
+                end
+            }
+            None => indent,
+        };
+        lex_inline(line, from, tokens)
+    }
+}
@@ -0,0 +1,362 @@
+use super::{Lexer, State, Token, TokenKind};
+
+#[derive(Clone, Copy)]
+pub struct StringDelimiter {
+    open: &'static str,
+    close: &'static str,
+    escapes: bool,
+    //goes on past the end of the line until it is closed
+    multi_line: bool,
+}
+
+const fn delimiter(open: &'static str, escapes: bool, multi_line: bool) -> StringDelimiter {
+    StringDelimiter {
+        open,
+        close: open,
+        escapes,
+        multi_line,
+    }
+}
+
+//lexer for languages that only differ in words and delimiters
+#[allow(clippy::struct_excessive_bools)]
+pub struct Syntax {
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    //identifiers starting with an uppercase letter are types too
+    capitalized_types: bool,
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    nested_comments: bool,
+    //tried in order, so """ has to come before "
+    strings: &'static [StringDelimiter],
+    //'a' and '\n', but not lifetimes like 'a
+    char_literals: bool,
+    //r"..." and r#"..."#
+    raw_strings: bool,
+    //#include and friends at the start of a line
+    preprocessor: bool,
+    //toml [table] headers
+    table_headers: bool,
+}
+
+const NONE: Syntax = Syntax {
+    keywords: &[],
+    types: &[],
+    capitalized_types: false,
+    line_comment: None,
+    block_comment: None,
+    nested_comments: false,
+    strings: &[],
+    char_literals: false,
+    raw_strings: false,
+    preprocessor: false,
+    table_headers: false,
+};
+
+pub const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+        "type", "union", "unsafe", "use", "where", "while",
+    ],
+    types: &[
+        "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "u8",
+        "u16", "u32", "u64", "u128", "usize",
+    ],
+    capitalized_types: true,
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    nested_comments: true,
+    strings: &[delimiter("\"", true, true)],
+    char_literals: true,
+    raw_strings: true,
+    ..NONE
+};
+
+pub const C: Syntax = Syntax {
+    keywords: &[
+        "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern",
+        "for", "goto", "if", "inline", "register", "restrict", "return", "sizeof", "static",
+        "struct", "switch", "typedef", "union", "volatile", "while", "NULL", "true", "false",
+    ],
+    types: &[
+        "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void",
+        "size_t", "ssize_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t",
+        "uint32_t", "uint64_t", "FILE",
+    ],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    strings: &[delimiter("\"", true, false)],
+    char_literals: true,
+    preprocessor: true,
+    ..NONE
+};
+
+pub const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "True", "False", "None", "self",
+    ],
+    types: &[
+        "bool",
+        "bytes",
+        "dict",
+        "float",
+        "frozenset",
+        "int",
+        "list",
+        "object",
+        "set",
+        "str",
+        "tuple",
+    ],
+    capitalized_types: true,
+    line_comment: Some("#"),
+    strings: &[
+        delimiter("\"\"\"", true, true),
+        delimiter("'''", true, true),
+        delimiter("\"", true, false),
+        delimiter("'", true, false),
+    ],
+    ..NONE
+};
+
+pub const JSON: Syntax = Syntax {
+    keywords: &["true", "false", "null"],
+    strings: &[delimiter("\"", true, false)],
+    ..NONE
+};
+
+pub const TOML: Syntax = Syntax {
+    keywords: &["true", "false"],
+    line_comment: Some("#"),
+    strings: &[
+        delimiter("\"\"\"", true, true),
+        delimiter("'''", false, true),
+        delimiter("\"", true, false),
+        delimiter("'", false, false),
+    ],
+    table_headers: true,
+    ..NONE
+};
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+//byte index right after `close`, escaped characters are skipped
+fn string_end(line: &str, from: usize, close: &str, escapes: bool) -> Option<usize> {
+    let mut chars = line[from..].char_indices();
+    while let Some((offset, char)) = chars.next() {
+        let at = from.saturating_add(offset);
+        if line[at..].starts_with(close) {
+            return Some(at.saturating_add(close.len()));
+        }
+        if escapes && char == '\\' {
+            chars.next();
+        }
+    }
+    None
+}
+
+//number of '#' of a raw string starting at the beginning of `rest`
+fn raw_string_start(rest: &str) -> Option<usize> {
+    let rest = rest.strip_prefix('b').unwrap_or(rest).strip_prefix('r')?;
+    let hashes = rest
+        .len()
+        .saturating_sub(rest.trim_start_matches('#').len());
+    rest[hashes..].starts_with('"').then_some(hashes)
+}
+
+//length of a char literal at the start of `rest`, None for a lifetime
+fn char_literal_len(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    let (_, char) = chars.next()?;
+    if char == '\\' {
+        let close = rest.get(3..)?.find('\'')?;
+        return Some(close.saturating_add(4));
+    }
+    let (offset, close) = chars.next()?;
+    (close == '\'').then_some(offset.saturating_add(1))
+}
+
+//digits, suffixes like u8 and the fraction, but not a range like 0..1
+fn number_len(rest: &str) -> usize {
+    let mut chars = rest.char_indices().peekable();
+    while let Some((offset, char)) = chars.next() {
+        let next_is_digit = chars.peek().is_some_and(|(_, next)| next.is_ascii_digit());
+        if !(is_word_char(char) || char == '.' && next_is_digit) {
+            return offset;
+        }
+    }
+    rest.len()
+}
+
+impl Syntax {
+    //end of a block comment `depth` levels deep, and the state after it
+    fn comment_end(&self, line: &str, from: usize, depth: usize) -> (usize, State) {
+        let Some((open, close)) = self.block_comment else {
+            return (line.len(), State::Normal);
+        };
+        let mut depth = depth;
+        let mut at = from;
+        while at < line.len() {
+            let rest = &line[at..];
+            if rest.starts_with(close) {
+                at = at.saturating_add(close.len());
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return (at, State::Normal);
+                }
+            } else if self.nested_comments && rest.starts_with(open) {
+                at = at.saturating_add(open.len());
+                depth = depth.saturating_add(1);
+            } else {
+                at = at.saturating_add(rest.chars().next().map_or(1, char::len_utf8));
+            }
+        }
+        (line.len(), State::BlockComment(depth))
+    }
+
+    //lexes a comment or string that started at `start` in `state` up to its end, scanning
+    //from `from`. returns where normal lexing goes on, or the state if it doesn't end on this line
+    fn close(
+        &self,
+        line: &str,
+        start: usize,
+        from: usize,
+        state: State,
+        tokens: &mut Vec<Token>,
+    ) -> Result<usize, State> {
+        let (kind, end, next) = match state {
+            State::Normal | State::CodeBlock => return Ok(from),
+            State::BlockComment(depth) => {
+                let (end, next) = self.comment_end(line, from, depth);
+                (TokenKind::Comment, end, next)
+            }
+            State::String { close, escapes } => match string_end(line, from, close, escapes) {
+                Some(end) => (TokenKind::String, end, State::Normal),
+                None => (TokenKind::String, line.len(), state),
+            },
+            State::RawString(hashes) => {
+                let close = format!("\"{}", "#".repeat(hashes));
+                match line[from..].find(&close) {
+                    Some(at) => (
+                        TokenKind::String,
+                        from.saturating_add(at).saturating_add(close.len()),
+                        State::Normal,
+                    ),
+                    None => (TokenKind::String, line.len(), state),
+                }
+            }
+        };
+        if end > start {
+            tokens.push(Token {
+                kind,
+                range: start..end,
+            });
+        }
+        if next == State::Normal {
+            Ok(end)
+        } else {
+            Err(next)
+        }
+    }
+
+    fn word_kind(&self, word: &str) -> Option<TokenKind> {
+        if self.keywords.contains(&word) {
+            Some(TokenKind::Keyword)
+        } else if self.types.contains(&word)
+            || self.capitalized_types && word.starts_with(|char: char| char.is_uppercase())
+        {
+            Some(TokenKind::Type)
+        } else {
+            None
+        }
+    }
+}
+
+impl Lexer for Syntax {
+    fn lex(&self, line: &str, state: State, tokens: &mut Vec<Token>) -> State {
+        let mut at = match self.close(line, 0, 0, state, tokens) {
+            Ok(at) => at,
+            Err(state) => return state,
+        };
+        let line_start = line.len().saturating_sub(line.trim_start().len());
+        while let Some(char) = line[at..].chars().next() {
+            let rest = &line[at..];
+            //comments and strings, `from` is right after the opening delimiter
+            let opened = if let Some((open, _)) = self
+                .block_comment
+                .filter(|(open, _)| rest.starts_with(open))
+            {
+                Some((open.len(), State::BlockComment(1), true))
+            } else if let Some(hashes) = raw_string_start(rest).filter(|_| self.raw_strings) {
+                let open = rest.find('"').unwrap_or(0).saturating_add(1);
+                Some((open, State::RawString(hashes), true))
+            } else {
+                self.strings
+                    .iter()
+                    .find(|string| rest.starts_with(string.open))
+                    .map(|string| {
+                        let state = State::String {
+                            close: string.close,
+                            escapes: string.escapes,
+                        };
+                        (string.open.len(), state, string.multi_line)
+                    })
+            };
+            if let Some((open, state, multi_line)) = opened {
+                match self.close(line, at, at.saturating_add(open), state, tokens) {
+                    Ok(end) => at = end,
+                    Err(state) if multi_line => return state,
+                    Err(_) => return State::Normal,
+                }
+                continue;
+            }
+            let (kind, len) = if self
+                .line_comment
+                .is_some_and(|prefix| rest.starts_with(prefix))
+            {
+                (Some(TokenKind::Comment), rest.len())
+            } else if let Some(len) =
+                char_literal_len(rest).filter(|_| self.char_literals && char == '\'')
+            {
+                (Some(TokenKind::String), len)
+            } else if self.preprocessor && char == '#' && at == line_start {
+                let len = rest[1..]
+                    .find(|char: char| !is_word_char(char))
+                    .unwrap_or(rest.len().saturating_sub(1))
+                    .saturating_add(1);
+                (Some(TokenKind::Keyword), len)
+            } else if self.table_headers && char == '[' && at == line_start {
+                let len = rest
+                    .rfind(']')
+                    .map_or(rest.len(), |end| end.saturating_add(1));
+                (Some(TokenKind::Type), len)
+            } else if char.is_ascii_digit() {
+                (Some(TokenKind::Number), number_len(rest))
+            } else if is_word_char(char) {
+                let len = rest
+                    .find(|char: char| !is_word_char(char))
+                    .unwrap_or(rest.len());
+                (self.word_kind(&rest[..len]), len)
+            } else {
+                (None, char.len_utf8())
+            };
+            let end = at.saturating_add(len);
+            if let Some(kind) = kind {
+                tokens.push(Token {
+                    kind,
+                    range: at..end,
+                });
+            }
+            at = end;
+        }
+        State::Normal
+    }
+}
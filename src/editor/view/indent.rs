@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum IndentStyle {
+    #[default]
+    Tabs,
+    Spaces,
+}
+
+impl FromStr for IndentStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tabs" | "tab" => Ok(Self::Tabs),
+            "spaces" | "space" => Ok(Self::Spaces),
+            _ => Err(format!("unknown indent style `{value}`")),
+        }
+    }
+}
+
+//`width` is the columns of one level when indenting with spaces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Indentation {
+    pub style: IndentStyle,
+    pub width: usize,
+}
+
+pub const DEFAULT_INDENT_WIDTH: usize = 4;
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self {
+            style: IndentStyle::default(),
+            width: DEFAULT_INDENT_WIDTH,
+        }
+    }
+}
+
+//only this many lines from the start of the file are looked at
+pub const DETECT_LINES: usize = 1000;
+
+impl Indentation {
+    //what Tab inserts at display column `column`
+    pub fn fill(self, column: usize) -> String {
+        match self.style {
+            IndentStyle::Tabs => "\t".to_string(),
+            IndentStyle::Spaces => {
+                let width = self.width.max(1);
+                " ".repeat(width.saturating_sub(column.checked_rem(width).unwrap_or(0)))
+            }
+        }
+    }
+
+    //spaces Backspace removes after `column` leading spaces, to the previous stop
+    pub fn unindent(self, column: usize) -> usize {
+        let width = self.width.max(1);
+        match column.checked_rem(width) {
+            Some(0) => width.min(column),
+            Some(rest) => rest,
+            None => 1,
+        }
+    }
+
+    //tabs if more lines start with one than with spaces, the width is the most
+    //common step between the leading spaces of a line and the line before it.
+    //unchanged if nothing is indented
+    pub fn detect<'a>(self, lines: impl Iterator<Item = &'a str>) -> Self {
+        let mut tab_lines: usize = 0;
+        let mut space_lines: usize = 0;
+        let mut steps: HashMap<usize, usize> = HashMap::new();
+        let mut previous: usize = 0;
+        for line in lines {
+            let content = line.trim_start_matches([' ', '\t']);
+            //blank lines and the ` * ` of block comments say nothing about the indentation
+            if content.is_empty() || content.starts_with('*') {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines = tab_lines.saturating_add(1);
+                continue;
+            }
+            let spaces = line
+                .len()
+                .saturating_sub(line.trim_start_matches(' ').len());
+            if spaces > 0 {
+                space_lines = space_lines.saturating_add(1);
+            }
+            let step = spaces.abs_diff(previous);
+            if (2..=8).contains(&step) {
+                let count = steps.entry(step).or_default();
+                *count = count.saturating_add(1);
+            }
+            previous = spaces;
+        }
+        if tab_lines == 0 && space_lines == 0 {
+            return self;
+        }
+        if tab_lines > space_lines {
+            return Self {
+                style: IndentStyle::Tabs,
+                ..self
+            };
+        }
+        //ties go to the smaller step
+        let width = steps
+            .into_iter()
+            .max_by_key(|(step, count)| (*count, std::cmp::Reverse(*step)))
+            .map_or(self.width, |(step, _)| step);
+        Self {
+            style: IndentStyle::Spaces,
+            width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spaces(width: usize) -> Indentation {
+        Indentation {
+            style: IndentStyle::Spaces,
+            width,
+        }
+    }
+
+    #[test]
+    fn detects_space_width() {
+        let text = "fn main() {\n  let a = 1;\n  if a {\n    b();\n  }\n}\n";
+        assert_eq!(Indentation::default().detect(text.lines()), spaces(2));
+    }
+
+    #[test]
+    fn detects_tabs() {
+        let text = "fn main() {\n\tlet a = 1;\n\tif a {\n\t\tb();\n\t}\n}\n";
+        let detected = spaces(2).detect(text.lines());
+        assert_eq!(detected.style, IndentStyle::Tabs);
+        assert_eq!(detected.width, 2);
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comment_stars() {
+        let text = "/**\n * doc\n */\nfn a() {\n    b();\n\n    c();\n}\n";
+        assert_eq!(Indentation::default().detect(text.lines()), spaces(4));
+    }
+
+    #[test]
+    fn unindented_text_keeps_the_setting() {
+        let text = "a\nb\n\nc\n";
+        assert_eq!(spaces(3).detect(text.lines()), spaces(3));
+        assert_eq!(spaces(3).detect(std::iter::empty()), spaces(3));
+    }
+
+    #[test]
+    fn equal_counts_pick_the_smaller_step() {
+        let text = "  a\n      b\n";
+        assert_eq!(Indentation::default().detect(text.lines()), spaces(2));
+    }
+
+    #[test]
+    fn fill_goes_to_the_next_stop() {
+        assert_eq!(spaces(4).fill(0), "    ");
+        assert_eq!(spaces(4).fill(1), "   ");
+        assert_eq!(spaces(4).fill(4), "    ");
+        assert_eq!(spaces(0).fill(3), " ");
+        assert_eq!(Indentation::default().fill(3), "\t");
+    }
+
+    #[test]
+    fn unindent_goes_to_the_previous_stop() {
+        assert_eq!(spaces(4).unindent(8), 4);
+        assert_eq!(spaces(4).unindent(6), 2);
+        assert_eq!(spaces(4).unindent(2), 2);
+        assert_eq!(spaces(4).unindent(0), 0);
+        assert_eq!(spaces(0).unindent(3), 1);
+    }
+}
@@ -1,23 +1,33 @@
+use super::encoding;
+use super::highlight::{Token, TokenKind};
+use regex::Regex;
 use std::ops::Range;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 use std::fmt;
 
+//columns a line without a tab width of its own uses between tab stops
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
 enum GraphemeWidth {
     Half,
     Full,
+    //up to the next tab stop, so it depends on where the tab is
+    Tab,
 }
 
 impl GraphemeWidth {
-    fn saturating_add(&self, other: usize) -> usize {
-        other.saturating_add(self.to_value())
+    //column after the grapheme if it starts at `column`
+    fn saturating_add(&self, column: usize, tab_width: usize) -> usize {
+        column.saturating_add(self.to_value(column, tab_width))
     }
     // Todo: implement From tarit?
-    fn to_value(&self) -> usize {
+    fn to_value(&self, column: usize, tab_width: usize) -> usize {
         match self {
             Self::Half => 1,
             Self::Full => 2,
+            Self::Tab => tab_width.saturating_sub(column.checked_rem(tab_width).unwrap_or(0)),
         }
     }
 
@@ -30,43 +40,73 @@ impl GraphemeWidth {
 }
 
 pub struct TextFragment {
-    grapheme: String,
+    start_byte_idx: usize,
+    end_byte_idx: usize,
     rendered_width: GraphemeWidth,
+    //where the grapheme is shown, counted from the start of the line
+    columns: Range<usize>,
     replacement: Option<char>,
 }
 
-#[derive(Default)]
+//a row of a wrapped line, `columns` are counted from the start of the line
+pub struct WrappedRow {
+    pub graphemes: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+//a token kind over the graphemes in `range`
+pub struct Annotation {
+    kind: TokenKind,
+    range: Range<usize>,
+}
+
+//graphemes are not stored per fragment,
+//fragments only point into `string` to keep memory usage low
 pub struct Line {
+    string: String,
     fragments: Vec<TextFragment>,
+    annotations: Vec<Annotation>,
+    tab_width: usize,
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::with_tab_width("", DEFAULT_TAB_WIDTH)
+    }
 }
 
 impl fmt::Display for Line {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let result = self
-            .fragments
-            .iter()
-            .fold(String::new(), |mut acc, fragment| {
-                acc.push_str(&fragment.grapheme);
-                acc
-            });
-        write!(f, "{result}")
+        write!(f, "{}", self.string)
     }
 }
 impl Line {
-    fn str_to_fragments(string: &str) -> Vec<TextFragment> {
+    fn str_to_fragments(string: &str, tab_width: usize) -> Vec<TextFragment> {
+        let mut column = 0;
         let fragments = string
-            .graphemes(true)
-            .map(|grapheme| {
+            .grapheme_indices(true)
+            .map(|(start_byte_idx, grapheme)| {
                 let (replacement, rendered_width) = Self::replacement_character(grapheme)
                     .map_or_else(
                         //use trait
                         || (None, GraphemeWidth::from_value(grapheme.width())),
-                        |replacement| (Some(replacement), GraphemeWidth::Half),
+                        |replacement| {
+                            let width = if grapheme == "\t" {
+                                GraphemeWidth::Tab
+                            } else {
+                                GraphemeWidth::Half
+                            };
+                            (Some(replacement), width)
+                        },
                     );
 
+                let start_column = column;
+                column = rendered_width.saturating_add(column, tab_width);
                 TextFragment {
-                    grapheme: grapheme.to_string(),
+                    start_byte_idx,
+                    end_byte_idx: start_byte_idx.saturating_add(grapheme.len()),
                     rendered_width,
+                    columns: start_column..column,
                     replacement,
                 }
             })
@@ -74,15 +114,71 @@ impl Line {
         fragments
     }
 
-    pub fn from(string: &str) -> Self {
-        let fragments = Self::str_to_fragments(string);
-        Self { fragments }
+    fn rebuild_fragments(&mut self) {
+        self.fragments = Self::str_to_fragments(&self.string, self.tab_width);
+    }
+
+    fn grapheme(&self, fragment: &TextFragment) -> &str {
+        &self.string[fragment.start_byte_idx..fragment.end_byte_idx]
+    }
+
+    //byte index of the grapheme at `grapheme_index`, end of string if out of range
+    fn byte_index(&self, grapheme_index: usize) -> usize {
+        self.fragments
+            .get(grapheme_index)
+            .map_or(self.string.len(), |fragment| fragment.start_byte_idx)
+    }
+
+    //tab stops every `tab_width` columns, at least 1
+    pub fn with_tab_width(string: &str, tab_width: usize) -> Self {
+        let tab_width = tab_width.max(1);
+        let fragments = Self::str_to_fragments(string, tab_width);
+        Self {
+            string: string.to_string(),
+            fragments,
+            annotations: Vec::new(),
+            tab_width,
+        }
+    }
+
+    //tokens cover bytes, a grapheme partly inside one counts as inside
+    pub fn annotate(&mut self, tokens: &[Token]) {
+        self.annotations = tokens
+            .iter()
+            .map(|token| Annotation {
+                kind: token.kind,
+                range: self
+                    .fragments
+                    .partition_point(|fragment| fragment.end_byte_idx <= token.range.start)
+                    ..self
+                        .fragments
+                        .partition_point(|fragment| fragment.start_byte_idx < token.range.end),
+            })
+            .filter(|annotation| !annotation.range.is_empty())
+            .collect();
+    }
+
+    fn kind_at(&self, grapheme_index: usize) -> Option<TokenKind> {
+        let index = self
+            .annotations
+            .partition_point(|annotation| annotation.range.end <= grapheme_index);
+        self.annotations
+            .get(index)
+            .filter(|annotation| annotation.range.start <= grapheme_index)
+            .map(|annotation| annotation.kind)
     }
 
     fn replacement_character(str: &str) -> Option<char> {
         //TODO: https://www.unicode.org/charts/PDF/U2400.pdf
         let width = str.width();
 
+        let mut chars = str.chars();
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            if encoding::is_escaped(ch) {
+                return Some('�');
+            }
+        }
+
         match str {
             " " => None,
             "\t" => Some(' '),
@@ -103,60 +199,200 @@ impl Line {
         }
     }
 
-    pub fn get_graphemes(&self, range: Range<usize>) -> String {
+    //calls `push` with the grapheme index and what is shown for every grapheme
+    //between the columns in `range`, graphemes cut off at either edge show as an ellipsis.
+    //a tab shows as blanks up to the next tab stop, only the ones in `range` if it is cut off
+    fn render(&self, range: Range<usize>, mut push: impl FnMut(usize, &str)) {
         if range.start > range.end {
-            return String::new();
+            return;
         }
-        let mut result = String::new();
-        let mut curr_position = 0;
-        for fragment in &self.fragments {
-            let fragment_end = fragment.rendered_width.saturating_add(curr_position);
+        let mut buffer = [0; 4];
+        let first = self
+            .fragments
+            .partition_point(|fragment| fragment.columns.end <= range.start);
+        for (index, fragment) in self.fragments.iter().enumerate().skip(first) {
+            let Range {
+                start: curr_position,
+                end: fragment_end,
+            } = fragment.columns.clone();
             if curr_position >= range.end {
                 break;
             }
-            if fragment_end > range.start {
-                if fragment_end > range.end || curr_position < range.start {
-                    //edge case: ellipsis
-                    result.push('⋯');
-                } else if let Some(char) = fragment.replacement {
-                    result.push(char);
-                } else {
-                    result.push_str(&fragment.grapheme);
-                }
+            if let GraphemeWidth::Tab = fragment.rendered_width {
+                let visible = fragment_end
+                    .min(range.end)
+                    .saturating_sub(curr_position.max(range.start));
+                push(index, &" ".repeat(visible));
+            } else if fragment_end > range.end || curr_position < range.start {
+                //edge case: ellipsis
+                push(index, "⋯");
+            } else if let Some(char) = fragment.replacement {
+                push(index, char.encode_utf8(&mut buffer));
+            } else {
+                push(index, self.grapheme(fragment));
             }
-            curr_position = fragment_end;
         }
+    }
+
+    pub fn get_graphemes(&self, range: Range<usize>) -> String {
+        let mut result = String::new();
+        self.render(range, |_, grapheme| result.push_str(grapheme));
         result
     }
 
+    //like `get_graphemes`, split into runs of the same token kind
+    pub fn get_spans(&self, range: Range<usize>) -> Vec<(Option<TokenKind>, String)> {
+        let mut spans: Vec<(Option<TokenKind>, String)> = Vec::new();
+        self.render(range, |index, grapheme| {
+            let kind = self.kind_at(index);
+            match spans.last_mut() {
+                Some((last, text)) if *last == kind => text.push_str(grapheme),
+                _ => spans.push((kind, grapheme.to_string())),
+            }
+        });
+        spans
+    }
+
+    //grapheme index of the fragment starting at `byte_index`, None inside a fragment
+    fn grapheme_index(&self, byte_index: usize) -> Option<usize> {
+        if byte_index == self.string.len() {
+            return Some(self.fragments.len());
+        }
+        self.fragments
+            .binary_search_by_key(&byte_index, |fragment| fragment.start_byte_idx)
+            .ok()
+    }
+
+    //grapheme ranges of every match of `pattern`. the regex engine works on bytes,
+    //matches that start or end inside a grapheme don't count so the cursor never
+    //lands inside a fragment
+    pub fn find_all(&self, pattern: &Regex) -> Vec<Range<usize>> {
+        pattern
+            .find_iter(&self.string)
+            .filter_map(|found| {
+                let start = self.grapheme_index(found.start())?;
+                let end = self.grapheme_index(found.end())?;
+                Some(start..end)
+            })
+            .collect()
+    }
+
+    //the match of `pattern` starting at grapheme `start` and `replacement` for it
+    //with $1 or ${name} expanded to the capture groups of the match
+    pub fn expand_match(
+        &self,
+        pattern: &Regex,
+        start: usize,
+        replacement: &str,
+    ) -> Option<(Range<usize>, String)> {
+        let start_byte = self.byte_index(start);
+        let captures = pattern.captures_iter(&self.string).find(|captures| {
+            captures
+                .get(0)
+                .is_some_and(|found| found.start() == start_byte)
+        })?;
+        let end = self.grapheme_index(captures.get(0)?.end())?;
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+        Some((start..end, expanded))
+    }
+
     pub fn grapheme_count(&self) -> usize {
         self.fragments.len()
     }
 
     pub fn width(&self) -> usize {
-        self.width_until(self.fragments.len())
+        self.fragments
+            .last()
+            .map_or(0, |fragment| fragment.columns.end)
     }
     pub fn width_until(&self, grapheme_index: usize) -> usize {
         self.fragments
-            .iter()
-            .take(grapheme_index)
-            .map(|grapheme| grapheme.rendered_width.to_value())
-            .sum()
+            .get(grapheme_index)
+            .map_or_else(|| self.width(), |fragment| fragment.columns.start)
     }
 
-    pub fn insert_char(&mut self, char: char, insert_index: usize) {
-        let mut result = String::new();
+    //grapheme ranges of the segments between unicode word boundaries and whether
+    //the segment is a word, not just blanks
+    fn word_segments(&self) -> Vec<(Range<usize>, bool)> {
+        self.string
+            .split_word_bound_indices()
+            .filter_map(|(byte_index, segment)| {
+                let start = self.grapheme_index(byte_index)?;
+                let end = self.grapheme_index(byte_index.saturating_add(segment.len()))?;
+                Some((start..end, !segment.trim().is_empty()))
+            })
+            .collect()
+    }
+
+    //grapheme index where the word before `x` starts, blanks in between are skipped
+    pub fn word_start_before(&self, x: usize) -> usize {
+        self.word_segments()
+            .into_iter()
+            .rev()
+            .find(|(range, is_word)| *is_word && range.start < x)
+            .map_or(0, |(range, _)| range.start)
+    }
+
+    //grapheme index where the word after `x` ends, blanks in between are skipped
+    pub fn word_end_after(&self, x: usize) -> usize {
+        self.word_segments()
+            .into_iter()
+            .find(|(range, is_word)| *is_word && range.end > x)
+            .map_or(self.grapheme_count(), |(range, _)| range.end)
+    }
+
+    //rows the line takes up when wrapped at `width` columns.
+    //a row ends at the last word boundary that fits, a word longer than a row is
+    //broken between graphemes, a wide grapheme is never split across rows
+    pub fn wrap(&self, width: usize) -> Vec<WrappedRow> {
+        let boundaries: Vec<usize> = self
+            .word_segments()
+            .into_iter()
+            .map(|(range, _)| range.start)
+            .collect();
+        let row = |graphemes: Range<usize>| WrappedRow {
+            columns: self.width_until(graphemes.start)..self.width_until(graphemes.end),
+            graphemes,
+        };
+        let mut rows = Vec::new();
+        let mut start = 0;
+        let mut row_column = 0;
         for (index, fragment) in self.fragments.iter().enumerate() {
-            if index == insert_index {
-                result.push(char);
+            while fragment.columns.end.saturating_sub(row_column) > width && index > start {
+                //the last boundary up to this grapheme, if the row has one
+                let end = boundaries
+                    .partition_point(|boundary| *boundary <= index)
+                    .checked_sub(1)
+                    .and_then(|before| boundaries.get(before))
+                    .copied()
+                    .filter(|boundary| *boundary > start)
+                    .unwrap_or(index);
+                rows.push(row(start..end));
+                start = end;
+                row_column = self.width_until(end);
             }
-            result.push_str(&fragment.grapheme);
-        }
-        if insert_index == self.fragments.len() {
-            result.push(char);
         }
+        rows.push(row(start..self.fragments.len()));
+        rows
+    }
 
-        self.fragments = Self::str_to_fragments(&result);
+    //grapheme of `row` shown `col` columns after the start of the row, the end of the row past it
+    pub fn grapheme_at(&self, row: &WrappedRow, col: usize) -> usize {
+        let column = row.columns.start.saturating_add(col);
+        let graphemes = self
+            .fragments
+            .get(row.graphemes.clone())
+            .unwrap_or_default();
+        row.graphemes
+            .start
+            .saturating_add(graphemes.partition_point(|fragment| fragment.columns.end <= column))
+    }
+
+    pub fn insert_char(&mut self, char: char, insert_index: usize) {
+        let byte_index = self.byte_index(insert_index);
+        self.string.insert(byte_index, char);
+        self.rebuild_fragments();
     }
 
     pub fn append_char(&mut self, char: char) {
@@ -164,34 +400,79 @@ impl Line {
     }
 
     pub fn remove_grapheme_at(&mut self, remove_index: usize) {
-        // do tutorial's way
-        let mut result = String::new();
-        for (index, fragment) in self.fragments.iter().enumerate() {
-            if index != remove_index {
-                result.push_str(&fragment.grapheme);
-            }
+        if let Some(fragment) = self.fragments.get(remove_index) {
+            let range = fragment.start_byte_idx..fragment.end_byte_idx;
+            self.string.replace_range(range, "");
+            self.rebuild_fragments();
         }
-        self.fragments = Self::str_to_fragments(&result);
     }
 
     pub fn remove_last(&mut self) {
         self.remove_grapheme_at(self.grapheme_count().saturating_sub(1));
     }
 
-    pub fn concat(&mut self, other: &Self) {
-        let self_iter = self.fragments.iter();
-        let result_str =
-            self_iter
-                .chain(other.fragments.iter())
-                .fold(String::new(), |mut acc, fragment| {
-                    acc.push_str(&fragment.grapheme);
-                    acc
-                });
-        self.fragments = Self::str_to_fragments(&result_str);
+    pub fn remove_word_backward(&mut self) {
+        let start = self.byte_index(self.word_start_before(self.grapheme_count()));
+        self.string.truncate(start);
+        self.rebuild_fragments();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(string: &str, width: usize) -> Vec<String> {
+        let line = Line::with_tab_width(string, DEFAULT_TAB_WIDTH);
+        line.wrap(width)
+            .into_iter()
+            .map(|row| {
+                let fragments = line.fragments.get(row.graphemes).unwrap_or_default();
+                fragments
+                    .iter()
+                    .map(|fragment| line.grapheme(fragment))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn short_lines_take_one_row() {
+        assert_eq!(rows("abc", 10), ["abc"]);
+        assert_eq!(rows("abc", 3), ["abc"]);
+        assert_eq!(rows("", 10), [""]);
+    }
+
+    #[test]
+    fn rows_end_at_word_breaks() {
+        assert_eq!(rows("hello world", 8), ["hello ", "world"]);
+        assert_eq!(rows("one two three", 9), ["one two ", "three"]);
     }
 
-    pub fn split_off(&mut self, index: usize) -> Self {
-        let fragments = self.fragments.split_off(index);
-        Self { fragments }
+    #[test]
+    fn long_words_are_broken_between_graphemes() {
+        assert_eq!(rows("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(
+            rows("a e\u{301}e\u{301}e\u{301}", 2),
+            ["a ", "e\u{301}e\u{301}", "e\u{301}"]
+        );
+    }
+
+    #[test]
+    fn wide_graphemes_are_never_split() {
+        assert_eq!(rows("日本語", 5), ["日本", "語"]);
+        assert_eq!(rows("a日本", 2), ["a", "日", "本"]);
+        //a row narrower than the grapheme still holds it whole
+        assert_eq!(rows("日本", 1), ["日", "本"]);
+    }
+
+    #[test]
+    fn row_columns_count_from_the_start_of_the_line() {
+        let line = Line::with_tab_width("ab日本", DEFAULT_TAB_WIDTH);
+        let columns: Vec<Range<usize>> = line.wrap(3).into_iter().map(|row| row.columns).collect();
+        assert_eq!(columns, [0..2, 2..4, 4..6]);
+        for row in line.wrap(3) {
+            assert!(row.columns.len() <= 3);
+        }
     }
 }
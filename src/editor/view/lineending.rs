@@ -0,0 +1,42 @@
+use std::fmt;
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Lf => Self::Crlf,
+            Self::Crlf => Self::Lf,
+        }
+    }
+
+    //the more common ending wins, ties go to LF
+    pub fn detect(lf_count: usize, crlf_count: usize) -> Self {
+        if crlf_count > lf_count {
+            Self::Crlf
+        } else {
+            Self::Lf
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lf => write!(f, "LF"),
+            Self::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::editor::terminal::Position;
+use std::cmp::Ordering;
 
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct Location {
@@ -14,3 +15,16 @@ impl From<Location> for Position {
         }
     }
 }
+
+//in reading order, line first
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,281 @@
+use super::buffer::Buffer;
+use super::location::Location;
+use crate::editor::terminal::Position;
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    //case sensitive only if the query has an uppercase letter
+    #[default]
+    Smart,
+    Ignore,
+    Match,
+}
+
+impl CaseMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Smart => Self::Ignore,
+            Self::Ignore => Self::Match,
+            Self::Match => Self::Smart,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case: CaseMode,
+    pub whole_word: bool,
+}
+
+impl fmt::Display for SearchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.regex { "regex" } else { "text" };
+        let case = match self.case {
+            CaseMode::Smart => "smart case",
+            CaseMode::Ignore => "ignore case",
+            CaseMode::Match => "match case",
+        };
+        write!(f, "{kind}, {case}")?;
+        if self.whole_word {
+            write!(f, ", whole word")?;
+        }
+        Ok(())
+    }
+}
+
+impl SearchOptions {
+    //plain text is escaped so both kinds of queries go through the regex engine
+    pub fn compile(self, query: &str) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        let ignore_case = match self.case {
+            CaseMode::Smart => !self.has_uppercase(query),
+            CaseMode::Ignore => true,
+            CaseMode::Match => false,
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+    }
+
+    //escapes like \W or \S in a regex are not letters the user typed
+    fn has_uppercase(self, query: &str) -> bool {
+        let mut chars = query.chars();
+        while let Some(char) = chars.next() {
+            if char == '\\' && self.regex {
+                chars.next();
+            } else if char.is_uppercase() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+//matches are replaced from the first one on to the end of the buffer,
+//then from the start of the buffer up to `stop` where the first one was
+#[derive(Clone)]
+pub struct Replacement {
+    pub text: String,
+    pub stop: Location,
+    pub wrapped: bool,
+    pub count: usize,
+}
+
+//where the search started, to go back there if it is cancelled.
+//`pattern` is None while the query is empty or not a valid regex
+#[derive(Clone, Default)]
+pub struct SearchInfo {
+    pub prev_location: Location,
+    pub prev_scroll_offset: Position,
+    pub prev_wrap_offset: usize,
+    pub pattern: Option<Regex>,
+    pub replacement: Option<Replacement>,
+}
+
+//first match at or after `from`, wrapping around at the end of the buffer
+pub fn find_forward(buffer: &Buffer, pattern: &Regex, from: Location) -> Option<Location> {
+    let line_count = buffer.line_count();
+    let from = if from.y < line_count {
+        from
+    } else {
+        Location::default()
+    };
+    //the line of `from` comes up again last, for the matches before `from`
+    for offset in 0..=line_count {
+        let y = from.y.saturating_add(offset).checked_rem(line_count)?;
+        let matches = buffer.line(y)?.find_all(pattern);
+        let found = if offset == 0 {
+            matches.into_iter().find(|found| found.start >= from.x)
+        } else {
+            matches.into_iter().next()
+        };
+        if let Some(found) = found {
+            return Some(Location { x: found.start, y });
+        }
+    }
+    None
+}
+
+//last match before `from`, wrapping around at the start of the buffer
+pub fn find_backward(buffer: &Buffer, pattern: &Regex, from: Location) -> Option<Location> {
+    let line_count = buffer.line_count();
+    let from = if from.y < line_count {
+        from
+    } else {
+        Location {
+            x: usize::MAX,
+            y: line_count.saturating_sub(1),
+        }
+    };
+    for offset in 0..=line_count {
+        let y = from
+            .y
+            .saturating_add(line_count)
+            .saturating_sub(offset)
+            .checked_rem(line_count)?;
+        let matches = buffer.line(y)?.find_all(pattern);
+        let found = if offset == 0 {
+            matches.into_iter().rev().find(|found| found.start < from.x)
+        } else {
+            matches.into_iter().next_back()
+        };
+        if let Some(found) = found {
+            return Some(Location { x: found.start, y });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::config::Config;
+
+    fn buffer(text: &str) -> Buffer {
+        Buffer::scratch(text, &Config::default())
+    }
+
+    fn pattern(query: &str, options: SearchOptions) -> Regex {
+        options.compile(query).unwrap()
+    }
+
+    fn at(x: usize, y: usize) -> Location {
+        Location { x, y }
+    }
+
+    #[test]
+    fn forward_wraps_around() {
+        let buffer = buffer("one two\nthree two\n");
+        let two = pattern("two", SearchOptions::default());
+        assert_eq!(find_forward(&buffer, &two, at(0, 0)), Some(at(4, 0)));
+        assert_eq!(find_forward(&buffer, &two, at(5, 0)), Some(at(6, 1)));
+        assert_eq!(find_forward(&buffer, &two, at(7, 1)), Some(at(4, 0)));
+        assert_eq!(find_forward(&buffer, &two, at(0, 9)), Some(at(4, 0)));
+    }
+
+    #[test]
+    fn backward_wraps_around() {
+        let buffer = buffer("one two\nthree two\n");
+        let two = pattern("two", SearchOptions::default());
+        assert_eq!(find_backward(&buffer, &two, at(6, 1)), Some(at(4, 0)));
+        assert_eq!(find_backward(&buffer, &two, at(4, 0)), Some(at(6, 1)));
+        assert_eq!(find_backward(&buffer, &two, at(0, 9)), Some(at(6, 1)));
+    }
+
+    #[test]
+    fn no_match_is_none() {
+        let buffer = buffer("one\ntwo\n");
+        let three = pattern("three", SearchOptions::default());
+        assert_eq!(find_forward(&buffer, &three, at(0, 0)), None);
+        assert_eq!(find_backward(&buffer, &three, at(0, 0)), None);
+        assert_eq!(find_forward(&self::buffer(""), &three, at(0, 0)), None);
+    }
+
+    #[test]
+    fn locations_are_graphemes_on_multi_byte_lines() {
+        //é is 2 bytes, 日本 6 and e + a combining accent one grapheme of 3 bytes
+        let buffer = buffer("é日本 x e\u{301} x\n");
+        let x = pattern("x", SearchOptions::default());
+        assert_eq!(find_forward(&buffer, &x, at(0, 0)), Some(at(4, 0)));
+        assert_eq!(find_forward(&buffer, &x, at(5, 0)), Some(at(8, 0)));
+        assert_eq!(find_backward(&buffer, &x, at(8, 0)), Some(at(4, 0)));
+    }
+
+    #[test]
+    fn matches_inside_a_grapheme_are_skipped() {
+        let buffer = buffer("e\u{301} e\n");
+        //the first e ends inside the grapheme e + accent
+        let e = pattern("e", SearchOptions::default());
+        assert_eq!(find_forward(&buffer, &e, at(0, 0)), Some(at(2, 0)));
+        let accent = pattern("\u{301}", SearchOptions::default());
+        assert_eq!(find_forward(&buffer, &accent, at(0, 0)), None);
+    }
+
+    #[test]
+    fn smart_case() {
+        let buffer = buffer("Word word\n");
+        let options = SearchOptions::default();
+        assert_eq!(
+            find_forward(&buffer, &pattern("word", options), at(0, 0)),
+            Some(at(0, 0))
+        );
+        assert_eq!(
+            find_forward(&buffer, &pattern("Word", options), at(1, 0)),
+            Some(at(0, 0))
+        );
+        let regex = SearchOptions {
+            regex: true,
+            ..options
+        };
+        //the \W escape isn't an uppercase letter
+        assert_eq!(
+            find_forward(&buffer, &pattern(r"\Wword", regex), at(0, 0)),
+            Some(at(4, 0))
+        );
+        assert!(!pattern(r"\WWORD", regex).is_match(" word"));
+    }
+
+    #[test]
+    fn case_modes() {
+        let ignore = SearchOptions {
+            case: CaseMode::Ignore,
+            ..SearchOptions::default()
+        };
+        let matching = SearchOptions {
+            case: CaseMode::Match,
+            ..SearchOptions::default()
+        };
+        assert!(pattern("WORD", ignore).is_match("word"));
+        assert!(!pattern("word", matching).is_match("Word"));
+    }
+
+    #[test]
+    fn whole_word() {
+        let buffer = buffer("cat concat cat_s cat\n");
+        let options = SearchOptions {
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        let cat = pattern("cat", options);
+        assert_eq!(find_forward(&buffer, &cat, at(1, 0)), Some(at(17, 0)));
+        assert_eq!(find_backward(&buffer, &cat, at(17, 0)), Some(at(0, 0)));
+        //the group keeps the boundaries around every alternative
+        let regex = SearchOptions {
+            regex: true,
+            ..options
+        };
+        assert!(!pattern("con|s", regex).is_match("concat cat_s"));
+    }
+}
@@ -0,0 +1,476 @@
+use super::{
+    statusbar::StatusBar,
+    terminal::{Position, Size, Terminal},
+    theme::theme,
+    uicomponent::UIComponent,
+    view::View,
+};
+
+//how much a pane grows or shrinks per resize command, in percent of its split
+const RESIZE_STEP: usize = 5;
+const MIN_PERCENT: usize = 10;
+const MAX_PERCENT: usize = 90;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    //panes above each other
+    Horizontal,
+    //panes next to each other with a separator column between them
+    Vertical,
+}
+
+#[derive(Clone, Copy)]
+pub enum FocusDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+//a view with its own status line below it
+pub struct Pane {
+    pub view: View,
+    statusbar: StatusBar,
+    origin: Position,
+    size: Size,
+}
+
+impl Pane {
+    fn new(view: View) -> Self {
+        Self {
+            view,
+            statusbar: StatusBar::default(),
+            origin: Position::default(),
+            size: Size::default(),
+        }
+    }
+
+    fn place(&mut self, origin: Position, size: Size) {
+        self.origin = origin;
+        self.size = size;
+        self.view.resize(self.view_size());
+        self.statusbar.resize(Size {
+            height: 1,
+            width: size.width,
+        });
+    }
+
+    pub fn view_size(&self) -> Size {
+        Size {
+            height: self.size.height.saturating_sub(1),
+            width: self.size.width,
+        }
+    }
+
+    pub fn origin(&self) -> Position {
+        self.origin
+    }
+
+    fn contains(&self, position: Position) -> bool {
+        (self.origin.row..self.origin.row.saturating_add(self.size.height)).contains(&position.row)
+            && (self.origin.col..self.origin.col.saturating_add(self.size.width))
+                .contains(&position.col)
+    }
+
+    //the caret of the view on screen
+    fn caret(&self) -> Position {
+        let caret = self.view.get_caret_location();
+        Position {
+            row: self.origin.row.saturating_add(caret.row),
+            col: self.origin.col.saturating_add(caret.col),
+        }
+    }
+}
+
+//leaves are indices into `Windows::panes`
+enum Node {
+    Pane(usize),
+    Split {
+        direction: SplitDirection,
+        //share of the first child
+        percent: usize,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+}
+
+impl Node {
+    fn collect_panes(&self, panes: &mut Vec<usize>) {
+        match self {
+            Node::Pane(index) => panes.push(*index),
+            Node::Split { first, second, .. } => {
+                first.collect_panes(panes);
+                second.collect_panes(panes);
+            }
+        }
+    }
+
+    fn contains(&self, pane: usize) -> bool {
+        match self {
+            Node::Pane(index) => *index == pane,
+            Node::Split { first, second, .. } => first.contains(pane) || second.contains(pane),
+        }
+    }
+
+    fn first_pane(&self) -> usize {
+        match self {
+            Node::Pane(index) => *index,
+            Node::Split { first, .. } => first.first_pane(),
+        }
+    }
+
+    fn split(&mut self, pane: usize, new_pane: usize, direction: SplitDirection) {
+        match self {
+            Node::Pane(index) if *index == pane => {
+                *self = Node::Split {
+                    direction,
+                    percent: 50,
+                    first: Box::new(Node::Pane(pane)),
+                    second: Box::new(Node::Pane(new_pane)),
+                };
+            }
+            Node::Pane(_) => {}
+            Node::Split { first, second, .. } => {
+                first.split(pane, new_pane, direction);
+                second.split(pane, new_pane, direction);
+            }
+        }
+    }
+
+    //replaces the split holding `pane` by the other child, returns the first pane of that child
+    fn remove(&mut self, pane: usize) -> Option<usize> {
+        let Node::Split { first, second, .. } = self else {
+            return None;
+        };
+        let sibling = if matches!(**first, Node::Pane(index) if index == pane) {
+            std::mem::replace(second.as_mut(), Node::Pane(pane))
+        } else if matches!(**second, Node::Pane(index) if index == pane) {
+            std::mem::replace(first.as_mut(), Node::Pane(pane))
+        } else {
+            return first.remove(pane).or_else(|| second.remove(pane));
+        };
+        let focus = sibling.first_pane();
+        *self = sibling;
+        Some(focus)
+    }
+
+    //panes after a removed one move down by one index
+    fn renumber(&mut self, removed: usize) {
+        match self {
+            Node::Pane(index) if *index > removed => *index = index.saturating_sub(1),
+            Node::Pane(_) => {}
+            Node::Split { first, second, .. } => {
+                first.renumber(removed);
+                second.renumber(removed);
+            }
+        }
+    }
+
+    //the innermost split in `direction` around `pane` gives it more or less room
+    fn resize(&mut self, pane: usize, direction: SplitDirection, grow: bool) -> bool {
+        let Node::Split {
+            direction: split_direction,
+            percent,
+            first,
+            second,
+        } = self
+        else {
+            return false;
+        };
+        let in_first = first.contains(pane);
+        if !in_first && !second.contains(pane) {
+            return false;
+        }
+        let inner = if in_first { first } else { second };
+        if inner.resize(pane, direction, grow) {
+            return true;
+        }
+        if *split_direction != direction {
+            return false;
+        }
+        *percent = if in_first == grow {
+            percent.saturating_add(RESIZE_STEP)
+        } else {
+            percent.saturating_sub(RESIZE_STEP)
+        }
+        .clamp(MIN_PERCENT, MAX_PERCENT);
+        true
+    }
+
+    #[allow(clippy::integer_division)]
+    fn layout(
+        &self,
+        origin: Position,
+        size: Size,
+        panes: &mut [Pane],
+        separators: &mut Vec<(Position, usize)>,
+    ) {
+        match self {
+            Node::Pane(index) => {
+                if let Some(pane) = panes.get_mut(*index) {
+                    pane.place(origin, size);
+                }
+            }
+            Node::Split {
+                direction: SplitDirection::Horizontal,
+                percent,
+                first,
+                second,
+            } => {
+                let first_height = (size.height.saturating_mul(*percent) / 100)
+                    .clamp(1, size.height.saturating_sub(1).max(1));
+                first.layout(
+                    origin,
+                    Size {
+                        height: first_height,
+                        width: size.width,
+                    },
+                    panes,
+                    separators,
+                );
+                second.layout(
+                    Position {
+                        row: origin.row.saturating_add(first_height),
+                        col: origin.col,
+                    },
+                    Size {
+                        height: size.height.saturating_sub(first_height),
+                        width: size.width,
+                    },
+                    panes,
+                    separators,
+                );
+            }
+            Node::Split {
+                direction: SplitDirection::Vertical,
+                percent,
+                first,
+                second,
+            } => {
+                let available = size.width.saturating_sub(1);
+                let first_width = (available.saturating_mul(*percent) / 100)
+                    .clamp(1, available.saturating_sub(1).max(1));
+                first.layout(
+                    origin,
+                    Size {
+                        height: size.height,
+                        width: first_width,
+                    },
+                    panes,
+                    separators,
+                );
+                let separator_col = origin.col.saturating_add(first_width);
+                separators.push((
+                    Position {
+                        row: origin.row,
+                        col: separator_col,
+                    },
+                    size.height,
+                ));
+                second.layout(
+                    Position {
+                        row: origin.row,
+                        col: separator_col.saturating_add(1),
+                    },
+                    Size {
+                        height: size.height,
+                        width: available.saturating_sub(first_width),
+                    },
+                    panes,
+                    separators,
+                );
+            }
+        }
+    }
+}
+
+//the panes of the editor and how they split the screen.
+//`focus` is the index of the focused pane in `panes`,
+//separators are the columns between panes next to each other
+pub struct Windows {
+    root: Node,
+    panes: Vec<Pane>,
+    focus: usize,
+    size: Size,
+    separators: Vec<(Position, usize)>,
+    redraw: bool,
+}
+
+impl Default for Windows {
+    fn default() -> Self {
+        Self::new(View::default())
+    }
+}
+
+impl Windows {
+    pub fn new(view: View) -> Self {
+        Self {
+            root: Node::Pane(0),
+            panes: vec![Pane::new(view)],
+            focus: 0,
+            size: Size::default(),
+            separators: Vec::new(),
+            redraw: true,
+        }
+    }
+
+    pub fn focused(&self) -> &Pane {
+        &self.panes[self.focus]
+    }
+
+    pub fn view(&self) -> &View {
+        &self.panes[self.focus].view
+    }
+
+    pub fn view_mut(&mut self) -> &mut View {
+        &mut self.panes[self.focus].view
+    }
+
+    pub fn views_mut(&mut self) -> impl Iterator<Item = &mut View> {
+        self.panes.iter_mut().map(|pane| &mut pane.view)
+    }
+
+    //the new pane shows the same buffer at the same place and gets the focus
+    pub fn split(&mut self, direction: SplitDirection) {
+        let new_pane = self.panes.len();
+        let view = self.view().clone();
+        self.open_beside(direction, view);
+        self.focus = new_pane;
+    }
+
+    //a new pane showing `view`, the focused pane keeps the focus
+    pub fn open_beside(&mut self, direction: SplitDirection, view: View) {
+        let new_pane = self.panes.len();
+        self.panes.push(Pane::new(view));
+        self.root.split(self.focus, new_pane, direction);
+        self.relayout();
+    }
+
+    //false if the focused pane is the last one
+    pub fn close_focused(&mut self) -> bool {
+        let closed = self.focus;
+        let Some(focus) = self.root.remove(closed) else {
+            return false;
+        };
+        self.panes.remove(closed);
+        self.root.renumber(closed);
+        self.focus = if focus > closed {
+            focus.saturating_sub(1)
+        } else {
+            focus
+        };
+        self.relayout();
+        true
+    }
+
+    //panes are cycled in layout order
+    pub fn focus_next(&mut self) {
+        let mut order = Vec::new();
+        self.root.collect_panes(&mut order);
+        let position = order.iter().position(|index| *index == self.focus);
+        let next = position
+            .and_then(|position| order.get(position.saturating_add(1)))
+            .or(order.first());
+        if let Some(next) = next {
+            self.focus = *next;
+        }
+    }
+
+    //moves to the pane just past the edge of the focused one, in line with the caret
+    pub fn focus_towards(&mut self, direction: FocusDirection) {
+        let pane = self.focused();
+        let caret = pane.caret();
+        let target = match direction {
+            FocusDirection::Up => pane.origin.row.checked_sub(1).map(|row| Position {
+                row,
+                col: caret.col,
+            }),
+            FocusDirection::Down => Some(Position {
+                row: pane.origin.row.saturating_add(pane.size.height),
+                col: caret.col,
+            }),
+            //skipping the separator column
+            FocusDirection::Left => pane.origin.col.checked_sub(2).map(|col| Position {
+                row: caret.row,
+                col,
+            }),
+            FocusDirection::Right => Some(Position {
+                row: caret.row,
+                col: pane
+                    .origin
+                    .col
+                    .saturating_add(pane.size.width)
+                    .saturating_add(1),
+            }),
+        };
+        if let Some(index) =
+            target.and_then(|target| self.panes.iter().position(|pane| pane.contains(target)))
+        {
+            self.focus = index;
+        }
+    }
+
+    pub fn resize_focused(&mut self, direction: SplitDirection, grow: bool) {
+        if self.root.resize(self.focus, direction, grow) {
+            self.relayout();
+        }
+    }
+
+    pub fn resize(&mut self, size: Size) {
+        self.size = size;
+        self.relayout();
+    }
+
+    fn relayout(&mut self) {
+        self.separators.clear();
+        self.root.layout(
+            Position::default(),
+            self.size,
+            &mut self.panes,
+            &mut self.separators,
+        );
+        self.redraw = true;
+    }
+
+    pub fn update_status(&mut self) {
+        for (index, pane) in self.panes.iter_mut().enumerate() {
+            pane.statusbar.update_status(pane.view.get_status());
+            pane.statusbar.set_focused(index == self.focus);
+        }
+    }
+
+    //`skip_focused` leaves the focused view alone, something else is drawn over it
+    pub fn render(&mut self, skip_focused: bool) {
+        for (index, pane) in self.panes.iter_mut().enumerate() {
+            if pane.size.height == 0 || pane.size.width == 0 {
+                continue;
+            }
+            if !(skip_focused && index == self.focus) {
+                pane.view.render(pane.origin);
+            }
+            pane.statusbar.render(Position {
+                row: pane
+                    .origin
+                    .row
+                    .saturating_add(pane.size.height.saturating_sub(1)),
+                col: pane.origin.col,
+            });
+        }
+        if self.redraw {
+            for (origin, height) in &self.separators {
+                for row in 0..*height {
+                    let at = Position {
+                        row: origin.row.saturating_add(row),
+                        col: origin.col,
+                    };
+                    let _ = Terminal::print_styled_at(at, 1, theme().text, "│");
+                }
+            }
+            self.redraw = false;
+        }
+    }
+
+    pub fn caret(&self) -> Position {
+        self.focused().caret()
+    }
+}